@@ -0,0 +1,439 @@
+// On-disk format of the app index (app_index.json in the app data dir). The file
+// carries a schema version; older files are brought up to date by the migration
+// hooks below instead of being thrown away, and unreadable files are moved aside and
+// reported rather than silently ignored.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use crate::discovery::AppInfo;
+use crate::log_error;
+
+// Bump when the layout of IndexFile or AppInfo changes incompatibly, and append a
+// migration from the previous version to MIGRATIONS
+pub const INDEX_VERSION: u64 = 4;
+
+pub const INDEX_FILE: &str = "app_index.json";
+// Version 1: `{ apps, timestamp }`, written before the index was versioned
+const LEGACY_CACHE_FILE: &str = "apps_cache.json";
+
+// Upgrades a document by one version
+type Migration = fn(Value) -> Result<Value, String>;
+
+// MIGRATIONS[n] turns a version n + 1 document into version n + 2
+const MIGRATIONS: &[Migration] = &[
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+];
+
+// Size and modification time of a shortcut file when it was parsed; a file whose
+// fingerprint still matches doesn't need to be read again
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fingerprint {
+    pub modified: u64,  // Milliseconds since the Unix epoch
+    pub size: u64,
+}
+
+impl Fingerprint {
+    pub fn of(metadata: &fs::Metadata) -> Self {
+        let modified = metadata.modified()
+            .ok()
+            .and_then(|time| time.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|since| since.as_millis() as u64)
+            .unwrap_or_default();
+
+        Self { modified, size: metadata.len() }
+    }
+
+    pub fn read(path: &Path) -> Option<Self> {
+        fs::metadata(path).ok().map(|metadata| Self::of(&metadata))
+    }
+}
+
+// Parse result for a single file, before deduplication and source tagging. `app` is
+// None for files that were read but aren't launchable entries.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct IndexedFile {
+    pub path: String,
+    pub fingerprint: Fingerprint,
+    pub app: Option<AppInfo>,
+}
+
+// Modification times of the directories a scan walked, plus the roots it started
+// from. A directory's mtime changes whenever an entry is added, removed or renamed in
+// it, so an unchanged snapshot means a rescan would find the same files.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirectorySnapshot {
+    pub roots: Vec<String>,
+    // None for directories that didn't exist at scan time
+    pub modified: HashMap<String, Option<u64>>,
+}
+
+impl DirectorySnapshot {
+    pub fn record(&mut self, dir: &Path) {
+        let modified = Fingerprint::read(dir).map(|fingerprint| fingerprint.modified);
+        self.modified.insert(dir.to_string_lossy().into_owned(), modified);
+    }
+
+    pub fn contains(&self, dir: &Path) -> bool {
+        self.modified.contains_key(dir.to_string_lossy().as_ref())
+    }
+
+    // Whether the scan roots are the same and no recorded directory has changed
+    pub fn is_current(&self, roots: &[PathBuf]) -> bool {
+        let roots: Vec<String> = roots.iter().map(|root| root.to_string_lossy().into_owned()).collect();
+        roots == self.roots
+            && self.modified.iter().all(|(dir, modified)| {
+                Fingerprint::read(Path::new(dir)).map(|fingerprint| fingerprint.modified) == *modified
+            })
+    }
+}
+
+// The index as held in memory
+#[derive(Debug, Clone, Default)]
+pub struct AppIndex {
+    pub timestamp: u64,  // Unix timestamp of the last scan or update
+    pub apps: Vec<AppInfo>,
+    pub files: HashMap<String, IndexedFile>,
+    pub directories: DirectorySnapshot,
+}
+
+impl AppIndex {
+    // Whether the index is at least `ttl` seconds old at `now` (a ttl of 0 never
+    // expires). An index from the future (the clock was set back) can't be aged
+    // reliably, so it counts as expired.
+    pub fn is_expired(&self, now: u64, ttl: u64) -> bool {
+        match now.checked_sub(self.timestamp) {
+            None => true,
+            Some(age) => ttl > 0 && age >= ttl,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexFile {
+    version: u64,
+    timestamp: u64,
+    apps: Vec<AppInfo>,
+    files: Vec<IndexedFile>,
+    directories: DirectorySnapshot,
+}
+
+// Read the index from `dir`, falling back to a legacy apps_cache.json. Ok(None) when
+// neither exists. A file that can't be read or parsed is renamed to *.corrupt so the
+// next write starts clean, and the error is returned for the caller to surface.
+pub fn read(dir: &Path) -> Result<Option<AppIndex>, String> {
+    let path = dir.join(INDEX_FILE);
+    let path = if path.exists() {
+        path
+    } else {
+        let legacy = dir.join(LEGACY_CACHE_FILE);
+        if !legacy.exists() {
+            return Ok(None);
+        }
+        legacy
+    };
+
+    match fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read app index {}: {}", path.display(), e))
+        .and_then(|contents| parse(&contents))
+    {
+        Ok(index) => Ok(Some(index)),
+        Err(e) => {
+            log_error(&e);
+            let corrupt = path.with_extension("json.corrupt");
+            if let Err(rename_err) = fs::rename(&path, &corrupt) {
+                log_error(&format!("Failed to move aside corrupt app index: {}", rename_err));
+            }
+            Err(e)
+        }
+    }
+}
+
+fn parse(contents: &str) -> Result<AppIndex, String> {
+    let mut document: Value = serde_json::from_str(contents)
+        .map_err(|e| format!("App index is not valid JSON: {}", e))?;
+
+    let version = document.get("version").and_then(Value::as_u64).unwrap_or(1);
+    if version == 0 || version > INDEX_VERSION {
+        return Err(format!("Unsupported app index version {} (expected at most {})", version, INDEX_VERSION));
+    }
+    for migration in &MIGRATIONS[(version - 1) as usize..] {
+        document = migration(document)?;
+    }
+
+    let file: IndexFile = serde_json::from_value(document)
+        .map_err(|e| format!("App index does not match version {}: {}", INDEX_VERSION, e))?;
+
+    Ok(AppIndex {
+        timestamp: file.timestamp,
+        apps: file.apps,
+        files: file.files.into_iter().map(|file| (file.path.clone(), file)).collect(),
+        directories: file.directories,
+    })
+}
+
+pub fn write(dir: &Path, index: &AppIndex) -> Result<(), String> {
+    let mut files: Vec<&IndexedFile> = index.files.values().collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    let document = serde_json::json!({
+        "version": INDEX_VERSION,
+        "timestamp": index.timestamp,
+        "apps": index.apps,
+        "files": files,
+        "directories": index.directories,
+    });
+    let contents = serde_json::to_vec(&document)
+        .map_err(|e| format!("Failed to serialize app index: {}", e))?;
+
+    let path = dir.join(INDEX_FILE);
+    write_atomic(&path, &contents)
+        .map_err(|e| format!("Failed to write app index {}: {}", path.display(), e))?;
+
+    // The legacy cache has been superseded once a current index is on disk
+    let legacy = dir.join(LEGACY_CACHE_FILE);
+    if legacy.exists() {
+        let _ = fs::remove_file(legacy);
+    }
+    Ok(())
+}
+
+// Write to a temporary file next to `path` and rename it into place, so a crash
+// mid-write never leaves a truncated file behind
+pub fn write_atomic(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    let temp_path = path.with_file_name(name);
+
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+// Legacy entries were parsed by an older scanner without fingerprints; keep them so
+// the launcher has something to show, but with timestamp 0 so the next read rescans
+fn migrate_v1_to_v2(document: Value) -> Result<Value, String> {
+    let apps = document.get("apps")
+        .cloned()
+        .ok_or_else(|| "Legacy app cache has no apps list".to_string())?;
+
+    Ok(serde_json::json!({
+        "version": 2,
+        "timestamp": 0,
+        "apps": apps,
+        "files": [],
+    }))
+}
+
+// Version 2 indexes carry no directory snapshot; an empty one never matches the
+// current scan roots, so the first read after upgrading rescans
+fn migrate_v2_to_v3(mut document: Value) -> Result<Value, String> {
+    let object = document.as_object_mut()
+        .ok_or_else(|| "App index is not a JSON object".to_string())?;
+    object.insert("version".into(), 3.into());
+    object.insert("directories".into(), serde_json::to_value(DirectorySnapshot::default())
+        .map_err(|e| e.to_string())?);
+    Ok(document)
+}
+
+// Version 3 entries for .url files came from a parser that ignored hotkeys, working
+// directories and the Unicode sections; dropping the file fingerprints makes the next
+// scan parse every shortcut again
+fn migrate_v3_to_v4(mut document: Value) -> Result<Value, String> {
+    let object = document.as_object_mut()
+        .ok_or_else(|| "App index is not a JSON object".to_string())?;
+    object.insert("version".into(), 4.into());
+    object.insert("timestamp".into(), 0.into());
+    object.insert("files".into(), Value::Array(Vec::new()));
+    Ok(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("axon-app-index-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn names(index: &AppIndex) -> Vec<&str> {
+        index.apps.iter().map(|app| app.name.as_str()).collect()
+    }
+
+    #[test]
+    fn legacy_cache_is_migrated() {
+        let dir = temp_dir("legacy");
+        fs::write(dir.join(LEGACY_CACHE_FILE), include_str!("../tests/fixtures/app_index/v1_apps_cache.json")).unwrap();
+
+        let index = read(&dir).unwrap().unwrap();
+        assert_eq!(names(&index), ["Notepad", "Example"]);
+        assert_eq!(index.apps[1].path, "C:\\Users\\user\\Favorites\\Example.url");
+        // Rescanned on the next read
+        assert_eq!(index.timestamp, 0);
+        assert!(index.files.is_empty());
+        assert_eq!(index.directories, DirectorySnapshot::default());
+
+        // Writing the current format retires the legacy file
+        write(&dir, &index).unwrap();
+        assert!(!dir.join(LEGACY_CACHE_FILE).exists());
+        assert_eq!(names(&read(&dir).unwrap().unwrap()), ["Notepad", "Example"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn each_migration_step() {
+        let v1: Value = serde_json::from_str(include_str!("../tests/fixtures/app_index/v1_apps_cache.json")).unwrap();
+        let v2 = migrate_v1_to_v2(v1).unwrap();
+        assert_eq!(v2["version"], 2);
+        assert_eq!(v2["timestamp"], 0);
+        assert_eq!(v2["apps"].as_array().unwrap().len(), 2);
+        assert_eq!(v2["files"], serde_json::json!([]));
+
+        let v2: Value = serde_json::from_str(include_str!("../tests/fixtures/app_index/v2.json")).unwrap();
+        let v3 = migrate_v2_to_v3(v2.clone()).unwrap();
+        assert_eq!(v3["version"], 3);
+        assert_eq!(v3["timestamp"], v2["timestamp"]);
+        assert_eq!(v3["files"], v2["files"]);
+        assert_eq!(v3["directories"], serde_json::json!({ "roots": [], "modified": {} }));
+
+        let v3: Value = serde_json::from_str(include_str!("../tests/fixtures/app_index/v3.json")).unwrap();
+        let v4 = migrate_v3_to_v4(v3.clone()).unwrap();
+        assert_eq!(v4["version"], 4);
+        assert_eq!(v4["timestamp"], 0);
+        assert_eq!(v4["files"], serde_json::json!([]));
+        assert_eq!(v4["apps"], v3["apps"]);
+        assert_eq!(v4["directories"], v3["directories"]);
+
+        assert!(migrate_v1_to_v2(serde_json::json!({ "timestamp": 1 })).is_err());
+        assert!(migrate_v2_to_v3(serde_json::json!([])).is_err());
+        assert!(migrate_v3_to_v4(serde_json::json!("index")).is_err());
+    }
+
+    #[test]
+    fn versioned_documents_load() {
+        let index = parse(include_str!("../tests/fixtures/app_index/v2.json")).unwrap();
+        assert_eq!(names(&index), ["Notepad"]);
+        assert_eq!(index.apps[0].target.as_deref(), Some("C:\\Windows\\System32\\notepad.exe"));
+        assert_eq!((index.timestamp, index.files.len()), (0, 0));
+
+        let index = parse(include_str!("../tests/fixtures/app_index/v3.json")).unwrap();
+        assert_eq!(names(&index), ["Example"]);
+        assert_eq!(index.apps[0].source, "internet_shortcuts");
+        assert_eq!(index.directories.roots, ["C:\\Users\\user\\Favorites"]);
+        assert_eq!((index.timestamp, index.files.len()), (0, 0));
+    }
+
+    #[test]
+    fn unreadable_indexes_are_moved_aside() {
+        let future = format!("{{\"version\": {}, \"apps\": []}}", INDEX_VERSION + 1);
+        for (name, contents) in [("future", future.as_str()), ("invalid", "{\"version\": 4, \"apps\": ["), ("zero", "{\"version\": 0}")] {
+            let dir = temp_dir(name);
+            fs::write(dir.join(INDEX_FILE), contents).unwrap();
+
+            assert!(read(&dir).is_err());
+            assert!(!dir.join(INDEX_FILE).exists());
+            assert_eq!(fs::read_to_string(dir.join("app_index.json.corrupt")).unwrap(), contents);
+            assert!(read(&dir).unwrap().is_none());
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+
+    #[test]
+    fn current_index_round_trips() {
+        let dir = temp_dir("round-trip");
+        let app = AppInfo { name: "Tool".into(), path: "/apps/tool.desktop".into(), ..Default::default() };
+        let file = IndexedFile {
+            path: app.path.clone(),
+            fingerprint: Fingerprint { modified: 1, size: 2 },
+            app: Some(app.clone()),
+        };
+        let index = AppIndex {
+            timestamp: 1700000000,
+            apps: vec![app],
+            files: HashMap::from([(file.path.clone(), file)]),
+            directories: DirectorySnapshot { roots: vec!["/apps".into()], modified: HashMap::from([("/apps".into(), Some(3))]) },
+        };
+        write(&dir, &index).unwrap();
+
+        let read_back = read(&dir).unwrap().unwrap();
+        assert_eq!(read_back.timestamp, index.timestamp);
+        assert_eq!(names(&read_back), ["Tool"]);
+        assert_eq!(read_back.files["/apps/tool.desktop"].fingerprint, Fingerprint { modified: 1, size: 2 });
+        assert_eq!(read_back.directories, index.directories);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn atomic_writes_replace_the_file() {
+        let dir = temp_dir("atomic");
+        let path = dir.join("data.json");
+
+        write_atomic(&path, b"first").unwrap();
+        write_atomic(&path, b"second").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert!(!dir.join("data.json.tmp").exists());
+
+        // A failed write leaves neither the target nor the temporary file
+        let missing = dir.join("missing").join("data.json");
+        assert!(write_atomic(&missing, b"data").is_err());
+        assert!(!dir.join("missing").exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn directory_snapshots() {
+        let dir = temp_dir("snapshot");
+        let later = dir.join("later");
+        let roots = vec![dir.clone()];
+        let mut snapshot = DirectorySnapshot {
+            roots: vec![dir.to_string_lossy().into_owned()],
+            ..Default::default()
+        };
+        snapshot.record(&dir);
+        snapshot.record(&later);
+        assert!(snapshot.contains(&later));
+        assert!(snapshot.is_current(&roots));
+        assert!(!snapshot.is_current(&[dir.clone(), later.clone()]));
+
+        // Adding a file changes the directory's mtime
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        fs::write(dir.join("new.desktop"), b"").unwrap();
+        assert!(!snapshot.is_current(&roots));
+        snapshot.record(&dir);
+        assert!(snapshot.is_current(&roots));
+
+        // A directory that didn't exist at scan time appearing
+        fs::create_dir(&later).unwrap();
+        assert!(!snapshot.is_current(&roots));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn ttl_expiry() {
+        let index = AppIndex { timestamp: 1000, ..Default::default() };
+        assert!(!index.is_expired(1000, 60));
+        assert!(!index.is_expired(1059, 60));
+        assert!(index.is_expired(1060, 60));
+        assert!(!index.is_expired(1_000_000, 0));
+        // Clock set back
+        assert!(index.is_expired(999, 60));
+        assert!(index.is_expired(999, 0));
+    }
+}
@@ -0,0 +1,547 @@
+// freedesktop.org Desktop Entry discovery: reads .desktop files from the XDG data
+// directories and turns the visible applications into AppInfo records.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use crate::discovery::AppInfo;
+use crate::log_error;
+
+// Desktop Entry "Categories" mapped onto the launcher's categories, checked in order
+const CATEGORY_MAP: &[(&str, &str)] = &[
+    ("Game", "Games"),
+    ("Development", "Development"),
+    ("AudioVideo", "Media"),
+    ("Audio", "Media"),
+    ("Video", "Media"),
+    ("Graphics", "Media"),
+    ("WebBrowser", "Web"),
+    ("Utility", "Utilities"),
+    ("System", "Utilities"),
+    ("Settings", "Utilities"),
+];
+
+#[derive(Debug, Clone, Default)]
+pub struct DesktopEntry {
+    pub path: PathBuf,
+    pub entry_type: String,
+    pub name: String,
+    pub comment: Option<String>,
+    pub exec: Option<String>,
+    pub try_exec: Option<String>,
+    pub working_dir: Option<String>,
+    pub icon: Option<String>,
+    pub categories: Vec<String>,
+    pub only_show_in: Vec<String>,
+    pub not_show_in: Vec<String>,
+    pub no_display: bool,
+    pub hidden: bool,
+}
+
+impl DesktopEntry {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        Self::parse(&content, path, &locale_variants())
+    }
+
+    // Parse the [Desktop Entry] group, picking localized values by the given
+    // locale variants (most specific first)
+    pub fn parse(content: &str, path: &Path, locales: &[String]) -> Result<Self, String> {
+        let mut values: HashMap<&str, &str> = HashMap::new();
+        let mut in_entry = false;
+
+        for line in content.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                in_entry = line == "[Desktop Entry]";
+                continue;
+            }
+            if !in_entry {
+                continue;
+            }
+            if let Some((key, value)) = line.split_once('=') {
+                // First occurrence wins, duplicates are invalid per the spec
+                values.entry(key.trim()).or_insert(value.trim());
+            }
+        }
+
+        if values.is_empty() {
+            return Err(format!("No [Desktop Entry] group in {}", path.display()));
+        }
+
+        let localized = |key: &str| -> Option<String> {
+            locales.iter()
+                .find_map(|locale| values.get(format!("{}[{}]", key, locale).as_str()))
+                .or_else(|| values.get(key))
+                .map(|value| unescape(value))
+        };
+        let string = |key: &str| values.get(key).map(|value| unescape(value));
+        let boolean = |key: &str| values.get(key).map(|value| *value == "true").unwrap_or(false);
+        let list = |key: &str| values.get(key).map(|value| split_list(value)).unwrap_or_default();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            entry_type: string("Type").unwrap_or_default(),
+            name: localized("Name").ok_or_else(|| format!("Missing Name in {}", path.display()))?,
+            comment: localized("Comment").or_else(|| localized("GenericName")),
+            exec: string("Exec"),
+            try_exec: string("TryExec"),
+            working_dir: string("Path").filter(|dir| !dir.is_empty()),
+            icon: localized("Icon").filter(|icon| !icon.is_empty()),
+            categories: list("Categories"),
+            only_show_in: list("OnlyShowIn"),
+            not_show_in: list("NotShowIn"),
+            no_display: boolean("NoDisplay"),
+            hidden: boolean("Hidden"),
+        })
+    }
+
+    // Whether the entry should appear in the launcher on the given desktops
+    pub fn is_visible(&self, current_desktops: &[String]) -> bool {
+        if self.entry_type != "Application" || self.no_display || self.hidden {
+            return false;
+        }
+        if self.exec.as_deref().is_none_or(str::is_empty) {
+            return false;
+        }
+        if !self.only_show_in.is_empty()
+            && !self.only_show_in.iter().any(|d| current_desktops.contains(d)) {
+            return false;
+        }
+        if self.not_show_in.iter().any(|d| current_desktops.contains(d)) {
+            return false;
+        }
+        match &self.try_exec {
+            Some(try_exec) if !try_exec.is_empty() => find_executable(try_exec).is_some(),
+            _ => true,
+        }
+    }
+
+    // Expand the Exec key into an argv, substituting field codes
+    pub fn expand_exec(&self, files: &[String]) -> Vec<String> {
+        let Some(exec) = &self.exec else { return Vec::new() };
+        let mut args = Vec::new();
+
+        for arg in split_exec(exec) {
+            match arg.as_str() {
+                "%f" | "%u" => args.extend(files.first().cloned()),
+                "%F" | "%U" => args.extend(files.iter().cloned()),
+                "%i" => {
+                    if let Some(icon) = &self.icon {
+                        args.push("--icon".into());
+                        args.push(icon.clone());
+                    }
+                }
+                _ => {
+                    let expanded = expand_field_codes(&arg, self);
+                    if !expanded.is_empty() {
+                        args.push(expanded);
+                    }
+                }
+            }
+        }
+
+        args
+    }
+
+    pub fn category(&self) -> String {
+        CATEGORY_MAP.iter()
+            .find(|(key, _)| self.categories.iter().any(|c| c == key))
+            .map(|(_, category)| category.to_string())
+            .unwrap_or_else(|| "Other".into())
+    }
+
+    pub fn to_app_info(&self) -> AppInfo {
+        let argv = self.expand_exec(&[]);
+        let target = argv.first()
+            .map(|program| find_executable(program)
+                .map(|p| p.to_string_lossy().into_owned())
+                .unwrap_or_else(|| program.clone()));
+        let arguments = (argv.len() > 1).then(|| argv[1..].iter()
+            .map(|arg| quote_arg(arg))
+            .collect::<Vec<_>>()
+            .join(" "));
+
+        AppInfo {
+            name: self.name.clone(),
+            path: self.path.to_string_lossy().into_owned(),
+            icon: None,
+            category: self.category(),
+            target,
+            arguments,
+            working_dir: self.working_dir.clone(),
+            icon_path: self.icon.clone(),
+            icon_index: 0,
+            show_command: None,
+            description: self.comment.clone(),
+            hotkey: None,
+            source: String::new(),
+            shortcuts: Vec::new(),
+        }
+    }
+}
+
+// $XDG_DATA_HOME/applications followed by each $XDG_DATA_DIRS/applications, in precedence order
+pub fn application_dirs() -> Vec<PathBuf> {
+    data_dirs().into_iter().map(|dir| dir.join("applications")).collect()
+}
+
+// $XDG_DATA_HOME followed by each of $XDG_DATA_DIRS, in precedence order
+pub fn data_dirs() -> Vec<PathBuf> {
+    let data_home = std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var("HOME").ok().map(|home| PathBuf::from(home).join(".local/share")));
+
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+
+    data_home.into_iter()
+        .chain(data_dirs.split(':').filter(|dir| !dir.is_empty()).map(PathBuf::from))
+        .collect()
+}
+
+pub fn scan_applications() -> Vec<AppInfo> {
+    let desktops = current_desktops();
+    let locales = locale_variants();
+    let mut seen_ids = HashSet::new();
+    let mut apps = Vec::new();
+
+    for dir in application_dirs() {
+        let mut files = Vec::new();
+        collect_desktop_files(&dir, &dir, &mut files);
+
+        for (id, path) in files {
+            // An entry in a higher-precedence directory shadows the same desktop file ID
+            // below it, even when it is hidden
+            if !seen_ids.insert(id) {
+                continue;
+            }
+
+            let entry = match fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| DesktopEntry::parse(&content, &path, &locales)) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    log_error(&e);
+                    continue;
+                }
+            };
+
+            if entry.is_visible(&desktops) {
+                apps.push(entry.to_app_info());
+            }
+        }
+    }
+
+    apps
+}
+
+// Desktop file ID is the path below the applications dir with '/' replaced by '-'
+fn collect_desktop_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_desktop_files(root, &path, files);
+        } else if path.extension().is_some_and(|ext| ext == "desktop") {
+            if let Ok(relative) = path.strip_prefix(root) {
+                let id = relative.to_string_lossy().replace('/', "-");
+                files.push((id, path));
+            }
+        }
+    }
+}
+
+pub fn current_desktops() -> Vec<String> {
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|d| !d.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+// Locale keys to try for LC_MESSAGES of the form lang_COUNTRY.ENCODING@MODIFIER
+fn locale_variants() -> Vec<String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|value| !value.is_empty())
+        .unwrap_or_default();
+
+    let (locale, modifier) = match locale.split_once('@') {
+        Some((locale, modifier)) => (locale, Some(modifier)),
+        None => (locale.as_str(), None),
+    };
+    let locale = locale.split('.').next().unwrap_or_default();
+    if locale.is_empty() || locale == "C" || locale == "POSIX" {
+        return Vec::new();
+    }
+    let (lang, country) = match locale.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (locale, None),
+    };
+
+    let mut variants = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        variants.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        variants.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        variants.push(format!("{}@{}", lang, modifier));
+    }
+    variants.push(lang.to_string());
+    variants
+}
+
+// Absolute paths are checked directly, bare names are looked up on $PATH
+pub fn find_executable(program: &str) -> Option<PathBuf> {
+    let is_executable = |path: &Path| {
+        fs::metadata(path)
+            .map(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+            .unwrap_or(false)
+    };
+
+    if program.contains('/') {
+        let path = PathBuf::from(program);
+        return is_executable(&path).then_some(path);
+    }
+
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+        .unwrap_or_default()
+        .into_iter()
+        .map(|dir| dir.join(program))
+        .find(|path| is_executable(path))
+}
+
+// Value escapes from the spec: \s \n \t \r \\
+fn unescape(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('s') => result.push(' '),
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+
+    result
+}
+
+// Semicolon-separated lists, where "\;" is a literal semicolon
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&';') => {
+                current.push(';');
+                chars.next();
+            }
+            ';' => items.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    items.push(current);
+
+    items.into_iter()
+        .map(|item| unescape(item.trim()))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+// Split an (already unescaped) Exec value into arguments. Inside double quotes
+// a backslash escapes ", `, $ and \.
+pub fn split_exec(exec: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    let mut chars = exec.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            '\\' if quoted => {
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            ' ' | '\t' if !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            _ => {
+                current.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+
+    args
+}
+
+// Field codes embedded inside a larger argument; file codes expand to nothing
+// here and deprecated codes are dropped
+fn expand_field_codes(arg: &str, entry: &DesktopEntry) -> String {
+    let mut result = String::with_capacity(arg.len());
+    let mut chars = arg.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => result.push('%'),
+            Some('c') => result.push_str(&entry.name),
+            Some('k') => result.push_str(&entry.path.to_string_lossy()),
+            Some(_) | None => {}
+        }
+    }
+
+    result
+}
+
+pub fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains(|c: char| c.is_whitespace() || c == '"' || c == '\\') {
+        return arg.to_string();
+    }
+    let escaped = arg.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("\"{}\"", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(content: &str) -> DesktopEntry {
+        DesktopEntry::parse(content, Path::new("/usr/share/applications/editor.desktop"), &[]).unwrap()
+    }
+
+    const EDITOR: &str = "[Desktop Entry]\nType=Application\nName=Editor\nExec=editor\nIcon=accessories-text-editor\n";
+
+    #[test]
+    fn parse_reads_only_the_desktop_entry_group() {
+        let content = "# Name=Commented out\n\
+            [Desktop Entry]\n\
+            Type=Application\n\
+            # Exec=commented\n\
+            Name=Editor\n\
+            Name[de]=Bearbeiter\n\
+            Name[de_DE]=Texteditor\n\
+            Comment=Edit text\\sfiles\n\
+            Exec=editor %F\n\
+            Categories=Utility;TextEditor;\n\
+            Name=Duplicate\n\
+            \n\
+            [Desktop Action new-window]\n\
+            Name=New Window\n\
+            Exec=editor --new-window\n";
+        let path = Path::new("/usr/share/applications/editor.desktop");
+        let locales = |locales: &[&str]| locales.iter().map(|locale| locale.to_string()).collect::<Vec<_>>();
+
+        let parsed = DesktopEntry::parse(content, path, &[]).unwrap();
+        assert_eq!(parsed.name, "Editor");
+        assert_eq!(parsed.exec.as_deref(), Some("editor %F"));
+        assert_eq!(parsed.comment.as_deref(), Some("Edit text files"));
+        assert_eq!(parsed.categories, ["Utility", "TextEditor"]);
+        assert_eq!(parsed.category(), "Utilities");
+
+        // Most specific locale first, then the unlocalized key
+        let name = |variants: &[&str]| DesktopEntry::parse(content, path, &locales(variants)).unwrap().name;
+        assert_eq!(name(&["de_DE", "de"]), "Texteditor");
+        assert_eq!(name(&["de_AT", "de"]), "Bearbeiter");
+        assert_eq!(name(&["fr_FR", "fr"]), "Editor");
+
+        assert!(DesktopEntry::parse("[Desktop Action x]\nName=X\n", path, &[]).is_err());
+        assert!(DesktopEntry::parse("[Desktop Entry]\nType=Application\n", path, &[]).is_err());
+    }
+
+    #[test]
+    fn visibility() {
+        let desktops = vec!["GNOME".to_string()];
+        let visible = |extra: &str| entry(&format!("{}{}", EDITOR, extra)).is_visible(&desktops);
+
+        assert!(visible(""));
+        assert!(!visible("NoDisplay=true\n"));
+        assert!(!visible("Hidden=true\n"));
+        assert!(visible("OnlyShowIn=KDE;GNOME;\n"));
+        assert!(!visible("OnlyShowIn=KDE;XFCE;\n"));
+        assert!(!visible("NotShowIn=GNOME;\n"));
+        assert!(visible("NotShowIn=KDE;\n"));
+        assert!(visible("TryExec=/bin/sh\n"));
+        assert!(!visible("TryExec=/nonexistent/axon-test-tool\n"));
+        assert!(!visible("TryExec=axon-test-tool-not-on-path\n"));
+        assert!(!entry(&EDITOR.replace("Type=Application", "Type=Link")).is_visible(&desktops));
+        assert!(!entry(&EDITOR.replace("Exec=editor", "Exec=")).is_visible(&desktops));
+    }
+
+    #[test]
+    fn exec_field_codes() {
+        let editor = entry(&EDITOR.replace(
+            "Exec=editor",
+            "Exec=editor %f %F %u %U %i %c %k 100%% --dir=%d %d %D %n %N %v %m --name=%c",
+        ));
+        let files = ["a.txt".to_string(), "b.txt".to_string()];
+
+        assert_eq!(editor.expand_exec(&files), [
+            "editor", "a.txt", "a.txt", "b.txt", "a.txt", "a.txt", "b.txt",
+            "--icon", "accessories-text-editor", "Editor", "/usr/share/applications/editor.desktop",
+            "100%", "--dir=", "--name=Editor",
+        ]);
+        // File codes expand to nothing without files, and %i without an icon
+        let no_icon = entry(&EDITOR.replace("Icon=accessories-text-editor\n", "").replace("Exec=editor", "Exec=editor %U %i"));
+        assert_eq!(no_icon.expand_exec(&[]), ["editor"]);
+    }
+
+    #[test]
+    fn exec_quoting() {
+        assert_eq!(
+            split_exec(r#""/opt/My App/bin" --msg "say \"hi\" \$HOME \`x\` \\"	"" plain"#),
+            ["/opt/My App/bin", "--msg", "say \"hi\" $HOME `x` \\", "", "plain"],
+        );
+        assert_eq!(split_exec("  editor   --new  "), ["editor", "--new"]);
+        assert_eq!(split_exec(""), Vec::<String>::new());
+        // Quotes may cover part of an argument
+        assert_eq!(split_exec(r#"--title="My Notes""#), ["--title=My Notes"]);
+    }
+
+    #[test]
+    fn list_with_escaped_separator() {
+        assert_eq!(split_list(r"GNOME;KDE\;Plasma; ;XFCE;"), ["GNOME", "KDE;Plasma", "XFCE"]);
+        assert_eq!(split_list(""), Vec::<String>::new());
+    }
+}
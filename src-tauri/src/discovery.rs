@@ -0,0 +1,1361 @@
+// App discovery shared by the Tauri commands and the file watcher: one AppInfo type,
+// one category classifier and one cache of the last scan.
+
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::panic::AssertUnwindSafe;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant, SystemTime};
+use serde::{Deserialize, Serialize};
+use crate::app_index::{self, AppIndex, DirectorySnapshot, Fingerprint, IndexedFile};
+use crate::internet_shortcut::InternetShortcut;
+use crate::lnk;
+use crate::log_error;
+use crate::settings::{self, AppSettings, ScanRoot};
+use crate::sources;
+
+// Windows system directories that never contain launchable shortcuts; also the
+// default exclude rules for user-added scan roots
+pub const SKIP_DIRS: &[&str] = &[
+    "windows", "system32", "systemapps", "syswow64",
+    "winsxs", "assembly", "microsoft.net"
+];
+
+// Substrings matched against the lowercased shortcut and target paths, checked in order
+const CATEGORY_RULES: &[(&str, &[&str])] = &[
+    ("Games", &["games", "\\steam\\"]),
+    ("Utilities", &["accessories", "system tools", "utilities"]),
+    ("Media", &["media", "\\adobe\\", "\\music\\", "\\video\\"]),
+    ("Development", &["development", "programming", "\\visual studio", "\\jetbrains\\", "\\git\\"]),
+];
+
+// Minimum gap between two progress callbacks of the same scan
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct AppInfo {
+    pub name: String,
+    // The file the entry was discovered from (.lnk, .url); this is what gets launched
+    pub path: String,
+    pub icon: Option<String>,
+    pub category: String,
+    // Resolved shortcut target, or the URL for internet shortcuts
+    #[serde(default)]
+    pub target: Option<String>,
+    // Launch details carried over from the shortcut
+    #[serde(default)]
+    pub arguments: Option<String>,
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    #[serde(default)]
+    pub icon_path: Option<String>,
+    #[serde(default)]
+    pub icon_index: i32,
+    #[serde(default)]
+    pub show_command: Option<u32>,
+    #[serde(default)]
+    pub description: Option<String>,
+    // Keyboard shortcut assigned to the shortcut file, e.g. "Ctrl+Alt+K"
+    #[serde(default)]
+    pub hotkey: Option<String>,
+    // Id of the AppSource that discovered the entry
+    #[serde(default)]
+    pub source: String,
+    // Every shortcut file merged into this entry by dedupe_apps, starting with `path`
+    #[serde(default)]
+    pub shortcuts: Vec<String>,
+}
+
+pub fn start_menu_paths() -> Vec<PathBuf> {
+    ["ProgramData", "APPDATA"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|root| PathBuf::from(root).join("Microsoft\\Windows\\Start Menu\\Programs"))
+        .collect()
+}
+
+// Payload of the scan://progress event
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct ScanProgress {
+    pub directories_visited: usize,
+    pub apps_found: usize,
+}
+
+// Payload of the scan://done event. A scan cancelled by a refresh isn't reported on
+// its own; whoever was waiting for it gets the refresh's result instead.
+#[derive(Serialize, Debug, Clone)]
+pub struct ScanDone {
+    pub apps_found: usize,
+    // Why the scan failed; apps_found is 0 then
+    pub error: Option<String>,
+}
+
+// Cancellation token and progress counters shared by the workers of one scan, plus
+// the settings it scans with and the per-file parse results of the previous scan so
+// unchanged files can be reused
+pub struct ScanContext<'a> {
+    generation: u64,
+    // Bumped by every new scan; once it moves past `generation` this scan has been
+    // superseded and stops at the next directory
+    current: &'a AtomicU64,
+    directories_visited: AtomicUsize,
+    apps_found: AtomicUsize,
+    last_report: Mutex<Instant>,
+    on_progress: &'a (dyn Fn(ScanProgress) + Sync),
+    previous: HashMap<String, IndexedFile>,
+    parsed: Mutex<HashMap<String, IndexedFile>>,
+    directories: Mutex<DirectorySnapshot>,
+    settings: AppSettings,
+}
+
+impl<'a> ScanContext<'a> {
+    // Start a new scan, cancelling any scan on the same counter that is still running
+    pub fn begin(
+        current: &'a AtomicU64,
+        on_progress: &'a (dyn Fn(ScanProgress) + Sync),
+        previous: HashMap<String, IndexedFile>,
+        settings: AppSettings,
+    ) -> Self {
+        let generation = current.fetch_add(1, Ordering::SeqCst) + 1;
+        Self {
+            generation,
+            current,
+            directories_visited: AtomicUsize::new(0),
+            apps_found: AtomicUsize::new(0),
+            last_report: Mutex::new(Instant::now()),
+            on_progress,
+            previous,
+            parsed: Mutex::new(HashMap::new()),
+            directories: Mutex::new(DirectorySnapshot::default()),
+            settings,
+        }
+    }
+
+    // Entry for a file, reusing the previous parse when its fingerprint is unchanged
+    fn parse_file(&self, path: &Path, metadata: &fs::Metadata) -> Option<AppInfo> {
+        let path_str = path.to_string_lossy().into_owned();
+        let fingerprint = Fingerprint::of(metadata);
+
+        let app = match self.previous.get(&path_str) {
+            Some(indexed) if indexed.fingerprint == fingerprint => indexed.app.clone(),
+            _ => app_from_file(path),
+        };
+
+        self.parsed.lock().unwrap().insert(path_str.clone(), IndexedFile {
+            path: path_str,
+            fingerprint,
+            app: app.clone(),
+        });
+        app
+    }
+
+    // Parse results of every file this scan looked at
+    pub fn take_parsed(&self) -> HashMap<String, IndexedFile> {
+        std::mem::take(&mut *self.parsed.lock().unwrap())
+    }
+
+    // Modification times of every directory this scan read
+    pub fn take_directories(&self) -> DirectorySnapshot {
+        std::mem::take(&mut *self.directories.lock().unwrap())
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.current.load(Ordering::SeqCst) != self.generation
+    }
+
+    pub fn progress(&self) -> ScanProgress {
+        ScanProgress {
+            directories_visited: self.directories_visited.load(Ordering::Relaxed),
+            apps_found: self.apps_found.load(Ordering::Relaxed),
+        }
+    }
+
+    // Count a finished directory, reporting progress at most every PROGRESS_INTERVAL
+    fn directory_done(&self, apps_found: usize) {
+        self.directories_visited.fetch_add(1, Ordering::Relaxed);
+        self.apps_found.fetch_add(apps_found, Ordering::Relaxed);
+
+        let Ok(mut last_report) = self.last_report.try_lock() else { return };
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            *last_report = Instant::now();
+            (self.on_progress)(self.progress());
+        }
+    }
+}
+
+// Every enabled discovery source
+pub fn scan_all(ctx: &ScanContext) -> Vec<AppInfo> {
+    let mut apps = Vec::new();
+
+    for source in sources::enabled_sources(&ctx.settings) {
+        if ctx.is_cancelled() {
+            break;
+        }
+        let found = source.enumerate(&ctx.settings, ctx);
+        log_error(&format!("Found {} apps from {}", found.len(), source.id()));
+        apps.extend(found);
+    }
+
+    let apps = dedupe_apps(apps);
+    log_error(&format!("Total apps found: {}", apps.len()));
+    apps
+}
+
+// Collapse entries that launch the same thing, e.g. one program linked from both the
+// all-users and the per-user Start Menu. Entries match when their normalized targets
+// and arguments are equal; entries without a target are never merged.
+//
+// Merge policy: the entry ranked first by `merge_rank` keeps its name, path, launch
+// details and category. A winner categorized as "Other" takes the first more specific
+// category among the others, and missing descriptions and icon locations are filled
+// in from the others. `shortcuts` records every merged file, winner first.
+pub fn dedupe_apps(apps: Vec<AppInfo>) -> Vec<AppInfo> {
+    let mut groups: Vec<Vec<AppInfo>> = Vec::new();
+    let mut index: HashMap<(String, String), usize> = HashMap::new();
+
+    for app in apps {
+        let key = app.target.as_deref()
+            .map(normalize_target)
+            .filter(|target| !target.is_empty())
+            .map(|target| (target, app.arguments.as_deref().unwrap_or("").trim().to_string()));
+
+        match key {
+            Some(key) => match index.get(&key) {
+                Some(&group) => groups[group].push(app),
+                None => {
+                    index.insert(key, groups.len());
+                    groups.push(vec![app]);
+                }
+            },
+            None => groups.push(vec![app]),
+        }
+    }
+
+    groups.into_iter().map(merge_group).collect()
+}
+
+fn merge_group(mut group: Vec<AppInfo>) -> AppInfo {
+    group.sort_by_cached_key(merge_rank);
+    let others = group.split_off(1);
+    let mut merged = group.remove(0);
+
+    if merged.category == "Other" {
+        if let Some(other) = others.iter().find(|app| app.category != "Other") {
+            merged.category = other.category.clone();
+        }
+    }
+    if merged.description.is_none() {
+        merged.description = others.iter().find_map(|app| app.description.clone());
+    }
+    if merged.hotkey.is_none() {
+        merged.hotkey = others.iter().find_map(|app| app.hotkey.clone());
+    }
+    if merged.icon_path.is_none() {
+        if let Some(other) = others.iter().find(|app| app.icon_path.is_some()) {
+            merged.icon_path = other.icon_path.clone();
+            merged.icon_index = other.icon_index;
+        }
+    }
+
+    if !others.is_empty() {
+        log_error(&format!("Merged {} duplicate shortcuts into {}", others.len(), merged.name));
+    }
+
+    // Entries merged by an earlier pass bring along the shortcuts they already hold
+    let mut shortcuts: Vec<String> = Vec::new();
+    for app in std::iter::once(&merged).chain(&others) {
+        for path in shortcut_paths(app) {
+            if !shortcuts.iter().any(|shortcut| shortcut == path) {
+                shortcuts.push(path.to_string());
+            }
+        }
+    }
+    merged.shortcuts = shortcuts;
+    merged
+}
+
+// The entry's own path followed by any shortcuts merged into it
+fn shortcut_paths(app: &AppInfo) -> impl Iterator<Item = &str> {
+    std::iter::once(app.path.as_str()).chain(app.shortcuts.iter().map(String::as_str))
+}
+
+// Sort key for entries with the same target, lowest wins:
+//  1. the source registered first (Start Menu before user scan roots)
+//  2. shortcuts before bare executables, whose file names are rarely display names
+//  3. the per-user Start Menu before the all-users one, as it holds the user's renames
+//  4. the shallowest, then alphabetically first path, to stay deterministic
+fn merge_rank(app: &AppInfo) -> (usize, bool, bool, usize, String) {
+    let source_rank = sources::registered_sources()
+        .iter()
+        .position(|source| source.id() == app.source)
+        .unwrap_or(usize::MAX);
+    let lower = app.path.to_lowercase();
+    let is_executable = [".exe", ".bat", ".cmd"].iter().any(|ext| lower.ends_with(ext));
+    let is_all_users = std::env::var("ProgramData")
+        .is_ok_and(|root| lower.starts_with(&root.to_lowercase()));
+    let depth = app.path.matches(['\\', '/']).count();
+
+    (source_rank, is_executable, is_all_users, depth, lower)
+}
+
+// Canonical form of a target for comparison: environment variables expanded, one
+// separator style, no trailing separator. Case is folded only on Windows, where the
+// file system is case-insensitive; folding elsewhere would merge distinct files such
+// as /opt/App and /opt/app, so Linux targets compare case-sensitively.
+pub fn normalize_target(target: &str) -> String {
+    let expanded = lnk::expand_env_vars(target.trim().trim_matches('"'));
+    let mut normalized = String::with_capacity(expanded.len());
+
+    for c in expanded.chars() {
+        let c = if c == '/' { '\\' } else { c };
+        // Keep the leading pair of a UNC path, collapse doubled separators elsewhere
+        if c == '\\' && normalized.ends_with('\\') && normalized.len() > 1 {
+            continue;
+        }
+        normalized.push(c);
+    }
+
+    let normalized = normalized.trim_end_matches('\\');
+    if cfg!(windows) {
+        normalized.to_lowercase()
+    } else {
+        normalized.to_string()
+    }
+}
+
+// Directories whose changes should trigger a rescan
+pub fn watch_paths() -> Vec<PathBuf> {
+    watch_paths_for(&settings::load_or_default())
+}
+
+fn watch_paths_for(settings: &AppSettings) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+
+    for source in sources::enabled_sources(settings) {
+        for path in source.watch_paths(settings) {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+
+    paths
+}
+
+const MATCH_OPTIONS: glob::MatchOptions = glob::MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+// Filters applied while walking a scan root
+pub struct ScanRules {
+    extensions: Vec<String>,
+    max_depth: Option<u32>,
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+}
+
+impl ScanRules {
+    // Built-in roots: every file with one of the extensions, minus the system directories
+    pub fn for_extensions(extensions: &[&str]) -> Self {
+        Self {
+            extensions: extensions.iter().map(|ext| ext.to_string()).collect(),
+            max_depth: None,
+            include: Vec::new(),
+            exclude: compile_patterns(&settings::default_scan_excludes()).unwrap_or_default(),
+        }
+    }
+
+    pub fn from_root(root: &ScanRoot) -> Result<Self, String> {
+        Ok(Self {
+            extensions: root.extensions.iter()
+                .map(|ext| ext.trim_start_matches('.').to_lowercase())
+                .collect(),
+            max_depth: root.max_depth,
+            include: compile_patterns(&root.include)?,
+            exclude: compile_patterns(&root.exclude)?,
+        })
+    }
+
+    // Whether a scan of `root` would pick up the file at `path`
+    pub fn matches(&self, root: &Path, path: &Path) -> bool {
+        let Ok(relative) = path.strip_prefix(root) else { return false };
+        let parts: Vec<String> = relative.components()
+            .map(|part| part.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if parts.is_empty() {
+            return false;
+        }
+
+        // The file's directory sits parts.len() - 1 levels below the root
+        if self.max_depth.is_some_and(|max| parts.len() as u32 - 1 > max) {
+            return false;
+        }
+
+        let mut entry_relative = String::new();
+        for part in &parts {
+            if !entry_relative.is_empty() {
+                entry_relative.push('/');
+            }
+            entry_relative.push_str(part);
+            if self.is_excluded(&entry_relative) {
+                return false;
+            }
+        }
+
+        self.accepts_file(path, &entry_relative)
+    }
+
+    fn is_excluded(&self, relative: &str) -> bool {
+        self.exclude.iter().any(|pattern| pattern.matches_with(relative, MATCH_OPTIONS))
+    }
+
+    fn accepts_file(&self, path: &Path, relative: &str) -> bool {
+        let extension = path.extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+
+        self.extensions.contains(&extension)
+            && (self.include.is_empty()
+                || self.include.iter().any(|pattern| pattern.matches_with(relative, MATCH_OPTIONS)))
+    }
+}
+
+fn compile_patterns(patterns: &[String]) -> Result<Vec<glob::Pattern>, String> {
+    patterns.iter()
+        .map(|pattern| glob::Pattern::new(&pattern.replace('\\', "/"))
+            .map_err(|e| format!("Invalid pattern '{}': {}", pattern, e)))
+        .collect()
+}
+
+// A directory waiting to be read by one of the scan workers
+struct ScanJob<'r> {
+    dir: PathBuf,
+    relative: String,
+    depth: u32,
+    rules: &'r ScanRules,
+}
+
+struct WorkQueue<'r> {
+    jobs: VecDeque<ScanJob<'r>>,
+    // Workers currently reading a directory, which may still queue subdirectories
+    active: usize,
+}
+
+// Collect entries below each root that pass its rules. Directories are read by a
+// pool of worker threads; the result is sorted by path so it doesn't depend on
+// which worker got there first.
+pub fn scan_directories(roots: &[(PathBuf, &ScanRules)], ctx: &ScanContext) -> Vec<AppInfo> {
+    let jobs = roots.iter()
+        .map(|(dir, rules)| ScanJob { dir: dir.clone(), relative: String::new(), depth: 0, rules })
+        .collect();
+    let queue = Mutex::new(WorkQueue { jobs, active: 0 });
+    let ready = Condvar::new();
+    let results = Mutex::new(Vec::new());
+
+    std::thread::scope(|scope| {
+        for _ in 0..worker_count() {
+            scope.spawn(|| scan_worker(&queue, &ready, &results, ctx));
+        }
+    });
+
+    let mut apps = results.into_inner().unwrap_or_default();
+    apps.sort_by(|a, b| a.path.cmp(&b.path));
+    apps
+}
+
+fn worker_count() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .clamp(2, 8)
+}
+
+fn scan_worker(queue: &Mutex<WorkQueue>, ready: &Condvar, results: &Mutex<Vec<AppInfo>>, ctx: &ScanContext) {
+    loop {
+        let job = {
+            let mut state = queue.lock().unwrap();
+            loop {
+                if ctx.is_cancelled() {
+                    return;
+                }
+                if let Some(job) = state.jobs.pop_front() {
+                    state.active += 1;
+                    break job;
+                }
+                if state.active == 0 {
+                    return;
+                }
+                // Wake up periodically so a cancelled scan doesn't leave idle workers behind
+                state = ready.wait_timeout(state, PROGRESS_INTERVAL).unwrap().0;
+            }
+        };
+
+        let (subdirs, apps) = scan_job(&job, ctx);
+        ctx.directory_done(apps.len());
+        if !apps.is_empty() {
+            results.lock().unwrap().extend(apps);
+        }
+
+        let mut state = queue.lock().unwrap();
+        state.jobs.extend(subdirs);
+        state.active -= 1;
+        drop(state);
+        ready.notify_all();
+    }
+}
+
+// Read a single directory: the subdirectories still to visit and the apps found in it
+fn scan_job<'r>(job: &ScanJob<'r>, ctx: &ScanContext) -> (Vec<ScanJob<'r>>, Vec<AppInfo>) {
+    let mut subdirs = Vec::new();
+    let mut apps = Vec::new();
+    ctx.directories.lock().unwrap().record(&job.dir);
+    let Ok(entries) = fs::read_dir(&job.dir) else { return (subdirs, apps) };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let entry_relative = if job.relative.is_empty() {
+            name
+        } else {
+            format!("{}/{}", job.relative, name)
+        };
+
+        if job.rules.is_excluded(&entry_relative) {
+            log_error(&format!("Skipping excluded path: {}", path.display()));
+            continue;
+        }
+
+        if path.is_dir() {
+            if job.rules.max_depth.is_none_or(|max| job.depth < max) {
+                subdirs.push(ScanJob {
+                    dir: path,
+                    relative: entry_relative,
+                    depth: job.depth + 1,
+                    rules: job.rules,
+                });
+            }
+        } else if job.rules.accepts_file(&path, &entry_relative) {
+            let Ok(metadata) = entry.metadata() else { continue };
+            let Some(app) = ctx.parse_file(&path, &metadata) else { continue };
+            log_error(&format!("Found app: {}", app.name));
+            apps.push(app);
+        }
+    }
+
+    (subdirs, apps)
+}
+
+// Build an entry from a single shortcut file, or None if it isn't one we handle
+pub fn app_from_file(path: &Path) -> Option<AppInfo> {
+    let ext = path.extension()?.to_string_lossy().to_lowercase();
+    let name = path.file_stem()?.to_string_lossy().into_owned();
+    let path_str = path.to_string_lossy().into_owned();
+
+    match ext.as_str() {
+        "lnk" => {
+            let link = match lnk::ShellLink::open(path) {
+                Ok(link) => link,
+                Err(e) => {
+                    log_error(&e);
+                    return None;
+                }
+            };
+            let target = link.resolve_target(path);
+            let icon_path = link.icon_path();
+            let category = categorize(&[Some(path_str.as_str()), target.as_deref()]);
+
+            Some(AppInfo {
+                name,
+                path: path_str,
+                icon: None,
+                category,
+                target,
+                arguments: link.arguments,
+                working_dir: link.working_dir.as_deref().map(lnk::expand_env_vars),
+                icon_path,
+                icon_index: link.icon_index,
+                show_command: Some(link.show_command),
+                description: link.name,
+                hotkey: lnk::format_hotkey(link.hotkey),
+                source: String::new(),
+                shortcuts: Vec::new(),
+            })
+        }
+        "url" => {
+            let shortcut = match InternetShortcut::open(path) {
+                Ok(shortcut) => shortcut,
+                Err(e) => {
+                    log_error(&e);
+                    return None;
+                }
+            };
+            let url = shortcut.target()?.to_string();
+
+            Some(AppInfo {
+                name,
+                path: path_str,
+                icon: None,
+                category: "Web".into(),
+                target: Some(url),
+                working_dir: shortcut.working_dir.as_deref().map(lnk::expand_env_vars),
+                icon_path: shortcut.icon_file.as_deref().map(lnk::expand_env_vars),
+                icon_index: shortcut.icon_index,
+                show_command: shortcut.show_command,
+                hotkey: lnk::format_hotkey(shortcut.hotkey),
+                ..Default::default()
+            })
+        }
+        "exe" | "bat" | "cmd" => {
+            let category = categorize(&[Some(path_str.as_str())]);
+            Some(AppInfo {
+                name,
+                path: path_str.clone(),
+                icon: None,
+                category,
+                target: Some(path_str),
+                ..Default::default()
+            })
+        }
+        #[cfg(target_os = "linux")]
+        "desktop" => {
+            let entry = crate::desktop_entry::DesktopEntry::open(path).ok()?;
+            entry.is_visible(&crate::desktop_entry::current_desktops())
+                .then(|| entry.to_app_info())
+        }
+        _ => None,
+    }
+}
+
+// Payload of the apps://changed event
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct AppChanges {
+    pub added: Vec<AppInfo>,
+    pub removed: Vec<AppInfo>,
+    pub updated: Vec<AppInfo>,
+}
+
+impl AppChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
+}
+
+// Fold file system changes into the cached app list without a full rescan. Only the
+// touched files, and the other shortcuts of any entry they were merged into, are
+// parsed again; directories are expanded to the files below them.
+pub fn apply_file_changes(cache: &AppCache, paths: &HashSet<PathBuf>) -> AppChanges {
+    let Some(AppIndex { apps: previous, mut files, mut directories, .. }) = cache.current() else {
+        // Nothing has been scanned yet, the next command does a full scan
+        return AppChanges::default();
+    };
+    let settings = cache.settings();
+    let sources = sources::enabled_sources(&settings);
+
+    let mut touched = BTreeSet::new();
+    for path in paths {
+        if path.is_dir() {
+            collect_files(path, &mut touched);
+        } else {
+            touched.insert(path.clone());
+        }
+    }
+
+    // A removed or renamed directory takes every entry below it along
+    let is_touched = |shortcut: &str| paths.iter().any(|path| Path::new(shortcut).starts_with(path));
+    let (affected, mut apps): (Vec<AppInfo>, Vec<AppInfo>) = previous.iter()
+        .cloned()
+        .partition(|app| shortcut_paths(app).any(is_touched));
+    for app in &affected {
+        touched.extend(shortcut_paths(app).map(PathBuf::from));
+    }
+
+    // Keep the directory snapshot in step, so the next read doesn't rescan for
+    // changes that have already been applied
+    let previous_directories = directories.clone();
+    for path in paths.iter().chain(&touched) {
+        for dir in [Some(path.as_path()), path.parent()].into_iter().flatten() {
+            let is_new_subdir = dir.is_dir()
+                && dir.parent().is_some_and(|parent| directories.contains(parent));
+            if directories.contains(dir) || is_new_subdir {
+                directories.record(dir);
+            }
+        }
+    }
+
+    for path in &touched {
+        let path_str = path.to_string_lossy().into_owned();
+        files.remove(&path_str);
+
+        let Some(fingerprint) = Fingerprint::read(path).filter(|_| path.is_file()) else { continue };
+        let Some(source) = sources.iter().find(|source| source.accepts(path, &settings)) else { continue };
+        let app = app_from_file(path);
+        files.insert(path_str.clone(), IndexedFile { path: path_str, fingerprint, app: app.clone() });
+
+        if let Some(mut app) = app {
+            app.source = source.id().to_string();
+            apps.push(app);
+        }
+    }
+
+    let apps = dedupe_apps(apps);
+    let changes = diff_apps(&previous, &apps);
+    if !changes.is_empty() {
+        log_error(&format!("Incremental update: {} added, {} removed, {} updated",
+            changes.added.len(), changes.removed.len(), changes.updated.len()));
+    }
+    if !changes.is_empty() || directories != previous_directories {
+        cache.store(apps, files, directories);
+    }
+    changes
+}
+
+fn collect_files(dir: &Path, files: &mut BTreeSet<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.insert(path);
+        }
+    }
+}
+
+// Entries are matched up by the path they launch from
+fn diff_apps(previous: &[AppInfo], current: &[AppInfo]) -> AppChanges {
+    let mut changes = AppChanges::default();
+
+    for app in current {
+        match previous.iter().find(|old| old.path == app.path) {
+            None => changes.added.push(app.clone()),
+            Some(old) if old != app => changes.updated.push(app.clone()),
+            Some(_) => {}
+        }
+    }
+    changes.removed = previous.iter()
+        .filter(|old| !current.iter().any(|app| app.path == old.path))
+        .cloned()
+        .collect();
+
+    changes
+}
+
+// First category whose keywords appear in any of the given paths
+pub fn categorize(paths: &[Option<&str>]) -> String {
+    for path in paths.iter().flatten() {
+        let path = path.replace('/', "\\").to_lowercase();
+        for (category, keywords) in CATEGORY_RULES {
+            if keywords.iter().any(|keyword| path.contains(keyword)) {
+                return category.to_string();
+            }
+        }
+    }
+    "Other".into()
+}
+
+// Size and age of the app index, for the get_app_index_stats command
+#[derive(Serialize, Debug, Clone)]
+pub struct AppIndexStats {
+    pub app_count: usize,
+    pub shortcut_count: usize,
+    // Unix time of the last full scan or incremental update; None before the first
+    pub last_updated: Option<u64>,
+    // Invalidated or past its TTL, so the next read rescans
+    pub stale: bool,
+    // Why the on-disk index was discarded when first read; kept for the whole session
+    pub load_error: Option<String>,
+    // Why the last write of the index failed, if it did
+    pub write_error: Option<String>,
+}
+
+// Last scan result, kept in memory and mirrored to the on-disk index (see
+// app_index.rs) so a restart doesn't need a full rescan. Owned by the AppManager in
+// Tauri's managed state; the file watcher keeps it current between scans.
+pub struct AppCache {
+    state: Mutex<Option<AppIndex>>,
+    // Set by invalidate, cleared by the next store
+    invalidated: AtomicBool,
+    // Reported through stats(); a successful write doesn't clear the load error, since
+    // the rescan it triggers usually happens before anyone has looked
+    load_error: Mutex<Option<String>>,
+    write_error: Mutex<Option<String>>,
+    dir: PathBuf,
+    // Settings to discover with; None reads settings.json on every use, so changes
+    // apply without a restart
+    settings: Option<AppSettings>,
+    // Generation counter of the ScanContexts scanning into this cache
+    generation: AtomicU64,
+    scans: Mutex<ScanState>,
+    scan_finished: Condvar,
+}
+
+// Bookkeeping that lets concurrent readers share one scan instead of each starting
+// (and cancelling) their own
+struct ScanState {
+    // Generation of the newest scan started
+    started: u64,
+    running: bool,
+    // Generation and outcome of the last scan that ran to the end
+    finished: u64,
+    result: Result<Vec<AppInfo>, String>,
+}
+
+impl Default for ScanState {
+    fn default() -> Self {
+        Self { started: 0, running: false, finished: 0, result: Ok(Vec::new()) }
+    }
+}
+
+impl Default for AppCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AppCache {
+    pub fn new() -> Self {
+        let cache_dir = tauri::api::path::app_data_dir(&tauri::Config::default())
+            .unwrap_or_else(|| PathBuf::from("."));
+        Self::in_dir(cache_dir)
+    }
+
+    pub(crate) fn in_dir(cache_dir: PathBuf) -> Self {
+        if !cache_dir.exists() {
+            let _ = fs::create_dir_all(&cache_dir);
+        }
+
+        Self {
+            state: Mutex::new(None),
+            invalidated: AtomicBool::new(false),
+            load_error: Mutex::new(None),
+            write_error: Mutex::new(None),
+            dir: cache_dir,
+            settings: None,
+            generation: AtomicU64::new(0),
+            scans: Mutex::new(ScanState::default()),
+            scan_finished: Condvar::new(),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_settings(cache_dir: PathBuf, settings: AppSettings) -> Self {
+        Self { settings: Some(settings), ..Self::in_dir(cache_dir) }
+    }
+
+    fn settings(&self) -> AppSettings {
+        self.settings.clone().unwrap_or_else(settings::load_or_default)
+    }
+
+    // In-memory state, loading the on-disk index on first use
+    fn lock(&self) -> MutexGuard<'_, Option<AppIndex>> {
+        let mut state = self.state.lock().unwrap();
+        if state.is_none() {
+            match app_index::read(&self.dir) {
+                Ok(Some(index)) => *state = Some(index),
+                Ok(None) => {}
+                Err(e) => {
+                    // Start over with an empty index, but keep the reason for get_app_index_stats
+                    *state = Some(AppIndex::default());
+                    *self.load_error.lock().unwrap() = Some(e);
+                }
+            }
+        }
+        state
+    }
+
+    // Fresh unless invalidated, older than the configured TTL, or any scanned
+    // directory changed since. An index from the future (the clock was set back)
+    // can't be aged reliably, so it counts as stale.
+    fn is_fresh(&self, index: &AppIndex) -> bool {
+        if self.invalidated.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        let settings = self.settings();
+        let now = now_secs();
+        if index.timestamp > now {
+            log_error("App index is newer than the system clock, rescanning");
+        }
+        if index.is_expired(now, settings.cache_ttl_secs) {
+            return false;
+        }
+
+        index.directories.is_current(&watch_paths_for(&settings))
+    }
+
+    // Cached apps if still fresh
+    pub fn get(&self) -> Option<Vec<AppInfo>> {
+        let state = self.lock();
+        let index = state.as_ref()?;
+        if self.is_fresh(index) {
+            Some(index.apps.clone())
+        } else {
+            None
+        }
+    }
+
+    // Last stored index regardless of age
+    pub fn current(&self) -> Option<AppIndex> {
+        self.lock().clone()
+    }
+
+    // Per-file parse results of the last scan, for reuse by the next one
+    pub fn files(&self) -> HashMap<String, IndexedFile> {
+        self.lock().as_ref().map(|index| index.files.clone()).unwrap_or_default()
+    }
+
+    pub fn store(&self, apps: Vec<AppInfo>, files: HashMap<String, IndexedFile>, directories: DirectorySnapshot) {
+        let index = AppIndex { timestamp: now_secs(), apps, files, directories };
+
+        let write_result = app_index::write(&self.dir, &index);
+        if let Err(e) = &write_result {
+            log_error(e);
+        }
+        *self.write_error.lock().unwrap() = write_result.err();
+
+        *self.state.lock().unwrap() = Some(index);
+        self.invalidated.store(false, Ordering::SeqCst);
+    }
+
+    // Force the next read to rescan, e.g. after the enabled sources changed
+    pub fn invalidate(&self) {
+        self.invalidated.store(true, Ordering::SeqCst);
+    }
+
+    // Entry for a shortcut path, including shortcuts merged into another entry
+    pub fn find(&self, path: &str) -> Option<AppInfo> {
+        let state = self.lock();
+        state.as_ref()?.apps.iter()
+            .find(|app| app.path == path || app.shortcuts.iter().any(|shortcut| shortcut == path))
+            .cloned()
+    }
+
+    pub fn stats(&self) -> AppIndexStats {
+        let state = self.lock();
+        let load_error = self.load_error.lock().unwrap().clone();
+        let write_error = self.write_error.lock().unwrap().clone();
+        match state.as_ref().filter(|index| index.timestamp > 0 || !index.apps.is_empty()) {
+            Some(index) => AppIndexStats {
+                app_count: index.apps.len(),
+                shortcut_count: index.apps.iter().map(|app| app.shortcuts.len().max(1)).sum(),
+                last_updated: Some(index.timestamp).filter(|&timestamp| timestamp > 0),
+                stale: !self.is_fresh(index),
+                load_error,
+                write_error,
+            },
+            None => AppIndexStats {
+                app_count: 0,
+                shortcut_count: 0,
+                last_updated: None,
+                stale: true,
+                load_error,
+                write_error,
+            },
+        }
+    }
+}
+
+// Cached apps, or a fresh scan when the cache is stale or a refresh is forced. Only
+// a forced refresh cancels a scan in flight; other callers wait for the running scan
+// and share its result, as does a caller whose own scan a refresh superseded.
+pub fn load_apps(
+    cache: &AppCache,
+    force_refresh: bool,
+    on_progress: &(dyn Fn(ScanProgress) + Sync),
+) -> Result<Vec<AppInfo>, String> {
+    if !force_refresh {
+        if let Some(apps) = cache.get() {
+            log_error("Using cached app list");
+            return Ok(apps);
+        }
+    }
+
+    let previous = cache.files();
+    let mut scans = cache.scans.lock().unwrap();
+    if !force_refresh {
+        if scans.running {
+            let running = scans.started;
+            return wait_for_scan(cache, scans, running);
+        }
+        // Another caller may have finished a scan while this one waited for the lock
+        if let Some(apps) = cache.get() {
+            return Ok(apps);
+        }
+    }
+
+    let ctx = ScanContext::begin(&cache.generation, on_progress, previous, cache.settings());
+    scans.started = ctx.generation;
+    scans.running = true;
+    drop(scans);
+
+    let result = std::panic::catch_unwind(AssertUnwindSafe(|| scan_into(cache, &ctx, on_progress)))
+        .unwrap_or_else(|_| Err("App scan failed unexpectedly".into()));
+
+    let mut scans = cache.scans.lock().unwrap();
+    if scans.started != ctx.generation {
+        log_error("Scan was superseded by a newer refresh, waiting for it");
+        let newer = scans.started;
+        return wait_for_scan(cache, scans, newer);
+    }
+    scans.running = false;
+    scans.finished = ctx.generation;
+    scans.result = result.clone();
+    drop(scans);
+    cache.scan_finished.notify_all();
+    result
+}
+
+// Result of the scan with the given generation, or of a refresh that superseded it
+fn wait_for_scan(
+    cache: &AppCache,
+    scans: MutexGuard<'_, ScanState>,
+    generation: u64,
+) -> Result<Vec<AppInfo>, String> {
+    let scans = cache.scan_finished
+        .wait_while(scans, |scans| scans.finished < generation)
+        .unwrap();
+    scans.result.clone()
+}
+
+// Scan every source and store the result, unless the scan gets cancelled
+fn scan_into(cache: &AppCache, ctx: &ScanContext, on_progress: &(dyn Fn(ScanProgress) + Sync)) -> Result<Vec<AppInfo>, String> {
+    let apps = scan_all(ctx);
+    if ctx.is_cancelled() {
+        return Err("Scan was superseded by a newer refresh".into());
+    }
+
+    // Roots that weren't walked directory by directory (or don't exist) still count
+    let roots = watch_paths_for(&ctx.settings);
+    let mut directories = ctx.take_directories();
+    for root in &roots {
+        if !directories.contains(root) {
+            directories.record(root);
+        }
+    }
+    directories.roots = roots.iter().map(|root| root.to_string_lossy().into_owned()).collect();
+
+    on_progress(ctx.progress());
+    cache.store(apps.clone(), ctx.take_parsed(), directories);
+    Ok(apps)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_target_unifies_separators() {
+        assert_eq!(normalize_target("\\\\server\\\\share\\tool.exe\\"), "\\\\server\\share\\tool.exe");
+        assert_eq!(normalize_target(" \"/opt/app//bin/app/\" "), "\\opt\\app\\bin\\app");
+    }
+
+    #[test]
+    #[cfg(windows)]
+    fn normalize_target_folds_case_on_windows() {
+        assert_eq!(normalize_target("C:\\Program Files\\App.EXE"), normalize_target("c:/program files/app.exe"));
+    }
+
+    #[test]
+    #[cfg(not(windows))]
+    fn normalize_target_keeps_case_elsewhere() {
+        assert_ne!(normalize_target("/opt/App/run"), normalize_target("/opt/app/run"));
+    }
+
+    #[test]
+    fn a_new_scan_cancels_only_scans_on_the_same_counter() {
+        let counter = AtomicU64::new(0);
+        let other = AtomicU64::new(0);
+        let first = ScanContext::begin(&counter, &|_| {}, HashMap::new(), AppSettings::default());
+        let unrelated = ScanContext::begin(&other, &|_| {}, HashMap::new(), AppSettings::default());
+        assert!(!first.is_cancelled());
+
+        let second = ScanContext::begin(&counter, &|_| {}, HashMap::new(), AppSettings::default());
+        assert!(first.is_cancelled());
+        assert!(!second.is_cancelled());
+        assert!(!unrelated.is_cancelled());
+    }
+
+    #[test]
+    fn concurrent_reads_and_a_refresh_all_get_apps() {
+        let root = temp_tree("concurrent-scan", &[("tool.exe", b""), ("games/game.exe", b"")]);
+        let dir = std::env::temp_dir().join(format!("axon-discovery-test-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let cache = AppCache::with_settings(dir.clone(), root_settings(&root));
+
+        let results: Vec<_> = std::thread::scope(|scope| {
+            let handles: Vec<_> = [false, false, true, false]
+                .into_iter()
+                .map(|force_refresh| {
+                    let cache = &cache;
+                    scope.spawn(move || load_apps(cache, force_refresh, &|_| {}))
+                })
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+        let _ = fs::remove_dir_all(&root);
+        let _ = fs::remove_dir_all(&dir);
+
+        for result in &results {
+            let mut found = names(result.as_ref().unwrap()).to_vec();
+            found.sort();
+            assert_eq!(found, ["game", "tool"]);
+        }
+        let scans = cache.scans.lock().unwrap();
+        assert!(!scans.running);
+        assert_eq!(scans.finished, scans.started);
+    }
+
+    fn app(name: &str, path: &str, target: &str, category: &str, source: &str) -> AppInfo {
+        AppInfo {
+            name: name.into(),
+            path: path.into(),
+            category: category.into(),
+            target: Some(target.into()),
+            source: source.into(),
+            ..Default::default()
+        }
+    }
+
+    // Fresh directory under the system temp dir holding the given files
+    fn temp_tree(name: &str, files: &[(&str, &[u8])]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("axon-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (path, contents) in files {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        root
+    }
+
+    fn names(apps: &[AppInfo]) -> Vec<&str> {
+        apps.iter().map(|app| app.name.as_str()).collect()
+    }
+
+    #[test]
+    fn dedupe_merges_shortcuts_to_the_same_target() {
+        let mut apps = vec![
+            app("Editor", "D:\\Tools\\editor.exe", "D:\\Tools\\editor.exe", "Development", "extra_directories"),
+            app("Editor", "C:\\Start Menu\\Programs\\Editor.lnk", "D:/Tools//editor.exe", "Other", "start_menu"),
+            app("Editor (copy)", "C:\\Start Menu\\Programs\\Old\\Editor.lnk", "D:\\Tools\\editor.exe", "Other", "start_menu"),
+            app("Editor safe mode", "C:\\Start Menu\\Programs\\Editor safe.lnk", "D:\\Tools\\editor.exe", "Other", "start_menu"),
+            AppInfo { name: "No target".into(), path: "C:\\a.lnk".into(), ..Default::default() },
+            AppInfo { name: "No target".into(), path: "C:\\b.lnk".into(), ..Default::default() },
+        ];
+        apps[3].arguments = Some("--safe-mode".into());
+        let apps = dedupe_apps(apps);
+
+        assert_eq!(names(&apps), ["Editor", "Editor safe mode", "No target", "No target"]);
+        // The Start Menu shortcut wins over the executable and the deeper shortcut, and
+        // picks up the more specific category
+        let editor = &apps[0];
+        assert_eq!(editor.path, "C:\\Start Menu\\Programs\\Editor.lnk");
+        assert_eq!(editor.category, "Development");
+        assert_eq!(editor.shortcuts, [
+            "C:\\Start Menu\\Programs\\Editor.lnk",
+            "C:\\Start Menu\\Programs\\Old\\Editor.lnk",
+            "D:\\Tools\\editor.exe",
+        ]);
+
+        // A second pass over merged entries is a no-op
+        let again = dedupe_apps(apps.clone());
+        assert_eq!(again, apps);
+    }
+
+    #[test]
+    fn categorize_matches_paths_case_insensitively() {
+        assert_eq!(categorize(&[Some("C:\\Start Menu\\Programs\\ACCESSORIES\\Notepad.lnk")]), "Utilities");
+        assert_eq!(categorize(&[Some("C:\\Start Menu\\Programs\\Chat.lnk"), Some("D:/Steam/steamapps/game.exe")]), "Games");
+        assert_eq!(categorize(&[Some("C:\\Start Menu\\Programs\\Chat.lnk"), None]), "Other");
+    }
+
+    // The old main.rs scanner: Start Menu shortcuts are listed under the .lnk path and
+    // categorized by the folder they sit in
+    #[test]
+    fn start_menu_shortcuts_keep_the_link_path() {
+        let root = temp_tree("start-menu", &[
+            ("Programs/Accessories/Example.lnk", include_bytes!("../tests/fixtures/lnk/local.lnk")),
+            ("Programs/Windows/Hidden.lnk", include_bytes!("../tests/fixtures/lnk/local.lnk")),
+            ("Programs/readme.txt", b"not a shortcut"),
+        ]);
+        let generation = AtomicU64::new(0);
+        let ctx = ScanContext::begin(&generation, &|_| {}, HashMap::new(), AppSettings::default());
+        let rules = ScanRules::for_extensions(&["lnk"]);
+        let apps = scan_directories(&[(root.clone(), &rules)], &ctx);
+        let _ = fs::remove_dir_all(&root);
+
+        assert_eq!(names(&apps), ["Example"]);
+        let example = &apps[0];
+        assert!(example.path.ends_with("Example.lnk"));
+        assert_eq!(example.target.as_deref(), Some("C:\\Program Files\\Example App\\example.exe"));
+        assert_eq!(example.arguments.as_deref(), Some("--profile \"Work Profile\" --new-window"));
+        assert_eq!(example.description.as_deref(), Some("Example App – Stable"));
+        assert_eq!(example.category, "Utilities");
+    }
+
+    // The old AppManager scanner: executables in user-added roots, launched directly and
+    // categorized by their own path, honouring the root's filters
+    #[test]
+    fn custom_roots_list_executables_by_path() {
+        let root = temp_tree("custom-root", &[
+            ("Development/ide.exe", b""),
+            ("tool.exe", b""),
+            ("deep/nested/dir/buried.exe", b""),
+            ("uninstall.exe", b""),
+            ("scripts/build.BAT", b""),
+            ("notes.txt", b""),
+        ]);
+        let scan_root = ScanRoot {
+            path: root.to_string_lossy().into_owned(),
+            max_depth: Some(1),
+            extensions: vec![".exe".into(), "bat".into()],
+            include: Vec::new(),
+            exclude: vec!["uninstall*".into()],
+        };
+        let generation = AtomicU64::new(0);
+        let ctx = ScanContext::begin(&generation, &|_| {}, HashMap::new(), AppSettings::default());
+        let rules = ScanRules::from_root(&scan_root).unwrap();
+        let apps = scan_directories(&[(root.clone(), &rules)], &ctx);
+
+        assert_eq!(names(&apps), ["ide", "build", "tool"]);
+        for app in &apps {
+            assert_eq!(app.target.as_deref(), Some(app.path.as_str()));
+        }
+        assert_eq!(apps[0].category, "Development");
+        assert_eq!(apps[2].category, "Other");
+        assert!(rules.matches(&root, &root.join("tool.exe")));
+        assert!(!rules.matches(&root, &root.join("deep/nested/dir/buried.exe")));
+        assert!(!rules.matches(&root, &root.join("uninstall.exe")));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn a_corrupt_index_stays_reported_after_the_rescan() {
+        let dir = std::env::temp_dir().join(format!("axon-corrupt-index-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(app_index::INDEX_FILE), "{ not json").unwrap();
+
+        let cache = AppCache::in_dir(dir.clone());
+        assert_eq!(cache.get(), None);
+        cache.store(Vec::new(), HashMap::new(), DirectorySnapshot::default());
+        let stats = cache.stats();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(stats.load_error.is_some_and(|e| e.contains("not valid JSON")));
+        assert_eq!(stats.write_error, None);
+    }
+
+    // Settings whose only enabled source is the scan root `root`
+    fn root_settings(root: &Path) -> AppSettings {
+        AppSettings {
+            scan_roots: vec![ScanRoot {
+                path: root.to_string_lossy().into_owned(),
+                max_depth: None,
+                extensions: vec!["exe".into(), "url".into()],
+                include: Vec::new(),
+                exclude: Vec::new(),
+            }],
+            disabled_sources: vec!["start_menu".into(), "internet_shortcuts".into(), "desktop_entries".into()],
+            ..Default::default()
+        }
+    }
+
+    fn url_file(url: &str) -> Vec<u8> {
+        format!("[InternetShortcut]\r\nURL={}\r\n", url).into_bytes()
+    }
+
+    #[test]
+    fn file_changes_add_update_and_remove_entries() {
+        let docs = url_file("https://docs.example.com/");
+        let root = temp_tree("file-changes", &[("Docs.url", &docs), ("tools/tool.exe", b"")]);
+        let dir = std::env::temp_dir().join(format!("axon-file-changes-index-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let cache = AppCache::with_settings(dir.clone(), root_settings(&root));
+        cache.store(Vec::new(), HashMap::new(), DirectorySnapshot::default());
+
+        // A new file and a new directory, which is expanded to the files below it
+        let changes = apply_file_changes(&cache, &HashSet::from([root.join("Docs.url"), root.join("tools")]));
+        let mut added = names(&changes.added);
+        added.sort();
+        assert_eq!(added, ["Docs", "tool"]);
+        assert!(changes.removed.is_empty() && changes.updated.is_empty());
+        assert!(changes.added.iter().all(|app| app.source == "extra_directories"));
+
+        fs::write(root.join("Docs.url"), url_file("https://docs.example.org/")).unwrap();
+        let changes = apply_file_changes(&cache, &HashSet::from([root.join("Docs.url")]));
+        assert_eq!(names(&changes.updated), ["Docs"]);
+        assert_eq!(changes.updated[0].target.as_deref(), Some("https://docs.example.org/"));
+        assert!(changes.added.is_empty() && changes.removed.is_empty());
+
+        fs::remove_dir_all(root.join("tools")).unwrap();
+        let changes = apply_file_changes(&cache, &HashSet::from([root.join("tools")]));
+        assert_eq!(names(&changes.removed), ["tool"]);
+        assert!(changes.added.is_empty() && changes.updated.is_empty());
+
+        // Nothing changed, nothing to report
+        let changes = apply_file_changes(&cache, &HashSet::from([root.join("Docs.url")]));
+        assert!(changes.is_empty());
+
+        let apps = cache.current().unwrap().apps;
+        let _ = fs::remove_dir_all(&root);
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!(names(&apps), ["Docs"]);
+    }
+
+    #[test]
+    fn file_changes_outside_enabled_sources_are_ignored() {
+        let root = temp_tree("ignored-changes", &[("tool.exe", b""), ("notes.txt", b"")]);
+        let elsewhere = temp_tree("ignored-changes-elsewhere", &[("other.exe", b"")]);
+        let dir = std::env::temp_dir().join(format!("axon-ignored-changes-index-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        // Outside the scan root, or not matching its extensions
+        let cache = AppCache::with_settings(dir.clone(), root_settings(&root));
+        cache.store(Vec::new(), HashMap::new(), DirectorySnapshot::default());
+        let outside = apply_file_changes(&cache, &HashSet::from([elsewhere.join("other.exe"), root.join("notes.txt")]));
+
+        // Under the scan root, but its source is switched off
+        let mut settings = root_settings(&root);
+        settings.disabled_sources.push("extra_directories".into());
+        let disabled_cache = AppCache::with_settings(dir.join("disabled"), settings);
+        disabled_cache.store(Vec::new(), HashMap::new(), DirectorySnapshot::default());
+        let disabled = apply_file_changes(&disabled_cache, &HashSet::from([root.join("tool.exe")]));
+
+        let _ = fs::remove_dir_all(&root);
+        let _ = fs::remove_dir_all(&elsewhere);
+        let _ = fs::remove_dir_all(&dir);
+        assert!(outside.is_empty());
+        assert!(disabled.is_empty());
+    }
+
+    #[test]
+    fn diff_apps_builds_the_changed_event_payload() {
+        let kept = app("Kept", "C:\\a.lnk", "C:\\a.exe", "Other", "start_menu");
+        let gone = app("Gone", "C:\\b.lnk", "C:\\b.exe", "Other", "start_menu");
+        let renamed = app("Old name", "C:\\c.lnk", "C:\\c.exe", "Other", "start_menu");
+        let new = app("New", "C:\\d.lnk", "C:\\d.exe", "Other", "start_menu");
+        let previous = vec![kept.clone(), gone, renamed];
+        let current = vec![kept, app("New name", "C:\\c.lnk", "C:\\c.exe", "Other", "start_menu"), new];
+
+        let payload = serde_json::to_value(diff_apps(&previous, &current)).unwrap();
+        let paths = |key: &str| -> Vec<String> {
+            payload[key].as_array().unwrap().iter().map(|app| app["path"].as_str().unwrap().to_string()).collect()
+        };
+        assert_eq!(paths("added"), ["C:\\d.lnk"]);
+        assert_eq!(paths("removed"), ["C:\\b.lnk"]);
+        assert_eq!(paths("updated"), ["C:\\c.lnk"]);
+        assert_eq!(payload["updated"][0]["name"], "New name");
+    }
+}
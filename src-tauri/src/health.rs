@@ -0,0 +1,358 @@
+// Health check over the app index: finds entries whose target no longer exists,
+// usually leftovers from an uninstall, so they can be reported or hidden instead of
+// failing when clicked.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::mpsc;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use crate::discovery::AppInfo;
+use crate::lnk;
+
+// How long to wait for a network share before calling it unreachable; an offline
+// server otherwise blocks a file system call for tens of seconds
+const SHARE_TIMEOUT: Duration = Duration::from_secs(2);
+// How long a share's reachability is remembered
+const SHARE_TTL: Duration = Duration::from_secs(60);
+// Age after which the last check's results are refreshed in the background
+const RESULTS_TTL: Duration = Duration::from_secs(60);
+
+// Reachability of \\server\share roots, keyed by the lowercased root
+static SHARES: OnceLock<Mutex<HashMap<String, (Instant, bool)>>> = OnceLock::new();
+// Outcome of the last check, for without_broken
+static RESULTS: OnceLock<Mutex<Option<CheckedApps>>> = OnceLock::new();
+
+struct CheckedApps {
+    // Digest of the checked entries' paths and targets
+    key: String,
+    checked: Instant,
+    broken: Vec<BrokenApp>,
+    refreshing: bool,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BrokenReason {
+    // The program or file the entry opens is gone
+    MissingTarget { target: String },
+    // The target is on a network share that doesn't answer
+    UnreachableNetworkPath { share: String },
+    // An internet shortcut whose URL can't be opened: malformed, or a file:// URL
+    // to something that no longer exists
+    DanglingUrl { url: String },
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct BrokenApp {
+    pub path: String,
+    pub name: String,
+    pub reason: BrokenReason,
+}
+
+// Every entry in `apps` that can't be launched. Network shares are checked once each,
+// in parallel, so a single offline server costs at most SHARE_TIMEOUT.
+pub fn check(apps: &[AppInfo]) -> Vec<BrokenApp> {
+    let broken = check_now(apps);
+    *RESULTS.get_or_init(Default::default).lock().unwrap() = Some(CheckedApps {
+        key: apps_key(apps),
+        checked: Instant::now(),
+        broken: broken.clone(),
+        refreshing: false,
+    });
+    broken
+}
+
+fn check_now(apps: &[AppInfo]) -> Vec<BrokenApp> {
+    let mut shares: Vec<String> = apps.iter()
+        .filter_map(local_target)
+        .filter_map(|target| share_root(&target))
+        .collect();
+    shares.sort();
+    shares.dedup();
+    let reachable = shares_reachable(&shares);
+
+    apps.iter()
+        .filter_map(|app| {
+            let reason = check_app(app, &reachable)?;
+            Some(BrokenApp { path: app.path.clone(), name: app.name.clone(), reason })
+        })
+        .collect()
+}
+
+// `apps` without the entries check() reports. Called for every app list and search
+// the frontend asks for, so it goes by the last check of the same entries.
+pub fn without_broken(apps: Vec<AppInfo>) -> Vec<AppInfo> {
+    let broken: HashSet<String> = cached_check(&apps).into_iter().map(|entry| entry.path).collect();
+    if broken.is_empty() {
+        return apps;
+    }
+    apps.into_iter()
+        .filter(|app| !broken.contains(&app.path))
+        .collect()
+}
+
+// The last check's results if it covered the same entries, else a new check. Results
+// older than RESULTS_TTL are still returned while a background check refreshes them,
+// so no caller waits on a share that went offline in the meantime.
+fn cached_check(apps: &[AppInfo]) -> Vec<BrokenApp> {
+    let key = apps_key(apps);
+    {
+        let mut results = RESULTS.get_or_init(Default::default).lock().unwrap();
+        if let Some(last) = results.as_mut().filter(|last| last.key == key) {
+            if last.checked.elapsed() >= RESULTS_TTL && !last.refreshing {
+                last.refreshing = true;
+                let apps = apps.to_vec();
+                std::thread::spawn(move || {
+                    check(&apps);
+                });
+            }
+            return last.broken.clone();
+        }
+    }
+    check(apps)
+}
+
+fn apps_key(apps: &[AppInfo]) -> String {
+    let mut context = md5::Context::new();
+    for app in apps {
+        context.consume(app.path.as_bytes());
+        context.consume([0]);
+        context.consume(app.target.as_deref().unwrap_or_default().as_bytes());
+        context.consume([0]);
+    }
+    format!("{:x}", context.compute())
+}
+
+fn check_app(app: &AppInfo, reachable: &HashMap<String, bool>) -> Option<BrokenReason> {
+    if is_internet_shortcut(app) {
+        return check_url(app.target.as_deref().unwrap_or_default());
+    }
+
+    let target = local_target(app)?;
+    if let Some(share) = share_root(&target) {
+        if !reachable.get(&share).copied().unwrap_or(false) {
+            return Some(BrokenReason::UnreachableNetworkPath { share });
+        }
+    }
+    (!Path::new(&target).exists()).then_some(BrokenReason::MissingTarget { target })
+}
+
+fn check_url(url: &str) -> Option<BrokenReason> {
+    let dangling = || Some(BrokenReason::DanglingUrl { url: url.to_string() });
+    let Ok(parsed) = url::Url::parse(url) else { return dangling() };
+
+    if parsed.scheme() == "file" {
+        let exists = parsed.to_file_path().is_ok_and(|path| path.exists());
+        if !exists {
+            return dangling();
+        }
+    }
+    None
+}
+
+fn is_internet_shortcut(app: &AppInfo) -> bool {
+    Path::new(&app.path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("url"))
+}
+
+// The entry's target as an absolute file system path. Targets that aren't paths,
+// such as shell namespace items or program names looked up on PATH, can't be checked
+// and yield None, as do paths with variables that don't expand on this machine.
+fn local_target(app: &AppInfo) -> Option<String> {
+    if is_internet_shortcut(app) {
+        return None;
+    }
+    let target = lnk::expand_env_vars(app.target.as_deref()?.trim());
+    if target.is_empty() || target.contains('%') {
+        return None;
+    }
+
+    let is_absolute = target.starts_with("\\\\")
+        || target.starts_with('/')
+        || target.as_bytes().get(1..3).is_some_and(|rest| rest[0] == b':' && matches!(rest[1], b'\\' | b'/'));
+    is_absolute.then_some(target)
+}
+
+// \\server\share of a UNC path, lowercased
+fn share_root(path: &str) -> Option<String> {
+    let rest = path.strip_prefix("\\\\")?;
+    // \\?\ and \\.\ paths are local device paths
+    if rest.starts_with("?\\") || rest.starts_with(".\\") {
+        return None;
+    }
+    let mut parts = rest.split(['\\', '/']).filter(|part| !part.is_empty());
+    let server = parts.next()?;
+    let share = parts.next()?;
+    Some(format!("\\\\{}\\{}", server, share).to_lowercase())
+}
+
+fn shares_reachable(shares: &[String]) -> HashMap<String, bool> {
+    let cache = SHARES.get_or_init(Default::default);
+    let mut result = HashMap::new();
+    let mut pending = Vec::new();
+
+    {
+        let cache = cache.lock().unwrap();
+        for share in shares {
+            match cache.get(share) {
+                Some((checked, reachable)) if checked.elapsed() < SHARE_TTL => {
+                    result.insert(share.clone(), *reachable);
+                }
+                _ => pending.push(share.clone()),
+            }
+        }
+    }
+    if pending.is_empty() {
+        return result;
+    }
+
+    // The probing threads are left behind if a share doesn't answer in time; they
+    // finish on their own once the OS gives up on the server
+    let (tx, rx) = mpsc::channel();
+    for share in &pending {
+        let tx = tx.clone();
+        let share = share.clone();
+        std::thread::spawn(move || {
+            let reachable = Path::new(&share).is_dir();
+            let _ = tx.send((share, reachable));
+        });
+    }
+    drop(tx);
+
+    let deadline = Instant::now() + SHARE_TIMEOUT;
+    let mut answered = HashMap::new();
+    while answered.len() < pending.len() {
+        let Some(remaining) = deadline.checked_duration_since(Instant::now()) else { break };
+        match rx.recv_timeout(remaining) {
+            Ok((share, reachable)) => {
+                answered.insert(share, reachable);
+            }
+            Err(_) => break,
+        }
+    }
+
+    let mut cache = cache.lock().unwrap();
+    let now = Instant::now();
+    for share in pending {
+        let reachable = answered.get(&share).copied().unwrap_or(false);
+        cache.insert(share.clone(), (now, reachable));
+        result.insert(share, reachable);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn app(path: &str, target: &str) -> AppInfo {
+        AppInfo { name: path.into(), path: path.into(), target: Some(target.into()), ..Default::default() }
+    }
+
+    fn temp_dir(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("axon-health-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn file_targets() {
+        let dir = temp_dir("files");
+        let present = dir.join("tool.exe");
+        fs::write(&present, b"").unwrap();
+        let missing = dir.join("gone.exe").to_string_lossy().into_owned();
+        let none = HashMap::new();
+
+        assert_eq!(check_app(&app("Tool.lnk", &present.to_string_lossy()), &none), None);
+        assert_eq!(
+            check_app(&app("Gone.lnk", &missing), &none),
+            Some(BrokenReason::MissingTarget { target: missing.clone() }),
+        );
+        // Targets that aren't absolute paths can't be checked and pass
+        assert_eq!(check_app(&app("Notepad.lnk", "notepad.exe"), &none), None);
+        assert_eq!(check_app(&app("Shell.lnk", "::{20D04FE0-3AEA-1069-A2D8-08002B30309D}"), &none), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unc_targets() {
+        let share = "\\\\server\\apps".to_string();
+        let target = "\\\\Server\\Apps\\tool.exe";
+        let offline = HashMap::from([(share.clone(), false)]);
+
+        assert_eq!(
+            check_app(&app("Tool.lnk", target), &offline),
+            Some(BrokenReason::UnreachableNetworkPath { share: share.clone() }),
+        );
+        // Unprobed shares count as unreachable
+        assert_eq!(
+            check_app(&app("Tool.lnk", target), &HashMap::new()),
+            Some(BrokenReason::UnreachableNetworkPath { share }),
+        );
+    }
+
+    #[test]
+    fn url_targets() {
+        let dir = temp_dir("urls");
+        let page = dir.join("page.html");
+        fs::write(&page, b"").unwrap();
+        let page_url = url::Url::from_file_path(&page).unwrap().to_string();
+        let dangling = |url: &str| Some(BrokenReason::DanglingUrl { url: url.to_string() });
+
+        assert_eq!(check_url("https://example.com/"), None);
+        assert_eq!(check_url("mailto:someone@example.com"), None);
+        assert_eq!(check_url(&page_url), None);
+        assert_eq!(check_url("file:///nonexistent/axon/page.html"), dangling("file:///nonexistent/axon/page.html"));
+        assert_eq!(check_url("not a url"), dangling("not a url"));
+        assert_eq!(check_app(&app("Site.url", "::bad"), &HashMap::new()), dangling("::bad"));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn share_roots() {
+        assert_eq!(share_root("\\\\Server\\Share\\dir\\tool.exe").as_deref(), Some("\\\\server\\share"));
+        assert_eq!(share_root("\\\\server/share/tool.exe").as_deref(), Some("\\\\server\\share"));
+        assert_eq!(share_root("\\\\server"), None);
+        assert_eq!(share_root("\\\\?\\C:\\tool.exe"), None);
+        assert_eq!(share_root("\\\\.\\pipe\\name"), None);
+        assert_eq!(share_root("C:\\tool.exe"), None);
+    }
+
+    #[test]
+    fn local_targets() {
+        let target = |path: &str, target: &str| local_target(&app(path, target));
+
+        assert_eq!(target("Tool.lnk", "C:\\Tools\\tool.exe").as_deref(), Some("C:\\Tools\\tool.exe"));
+        assert_eq!(target("Tool.lnk", "d:/tools/tool.exe").as_deref(), Some("d:/tools/tool.exe"));
+        assert_eq!(target("tool.desktop", "/usr/bin/tool").as_deref(), Some("/usr/bin/tool"));
+        assert_eq!(target("Tool.lnk", "\\\\server\\share\\tool.exe").as_deref(), Some("\\\\server\\share\\tool.exe"));
+        assert_eq!(target("Tool.lnk", "tool.exe"), None);
+        assert_eq!(target("Tool.lnk", "C:tool.exe"), None);
+        assert_eq!(target("Tool.lnk", "%AXON_UNSET_VARIABLE%\\tool.exe"), None);
+        assert_eq!(target("Tool.lnk", "  "), None);
+        assert_eq!(target("Site.url", "C:\\page.html"), None);
+    }
+
+    #[test]
+    fn without_broken_reuses_the_last_check() {
+        let dir = temp_dir("cache");
+        let present = dir.join("tool").to_string_lossy().into_owned();
+        let missing = dir.join("later").to_string_lossy().into_owned();
+        fs::write(&present, b"").unwrap();
+        let apps = vec![app("/apps/tool.desktop", &present), app("/apps/later.desktop", &missing)];
+        let paths = |apps: Vec<AppInfo>| apps.into_iter().map(|app| app.path).collect::<Vec<_>>();
+
+        assert_eq!(paths(without_broken(apps.clone())), ["/apps/tool.desktop"]);
+        // The cached result stands until the next check of the same entries
+        fs::write(&missing, b"").unwrap();
+        assert_eq!(paths(without_broken(apps.clone())), ["/apps/tool.desktop"]);
+        assert!(check(&apps).is_empty());
+        assert_eq!(paths(without_broken(apps)), ["/apps/tool.desktop", "/apps/later.desktop"]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
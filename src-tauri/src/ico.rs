@@ -0,0 +1,509 @@
+// Pure-Rust decoder for Windows icons: .ico files and the RT_GROUP_ICON/RT_ICON
+// resources of executables and DLLs. Works on raw bytes, so icon locations can be
+// read without the shell on any OS.
+
+use std::fs;
+use std::path::Path;
+use image::{ImageFormat, RgbaImage};
+
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+// Resource types
+const RT_ICON: u32 = 3;
+const RT_GROUP_ICON: u32 = 14;
+
+const IMAGE_DIRECTORY_ENTRY_RESOURCE: usize = 2;
+const PE32_MAGIC: u16 = 0x10B;
+const PE32_PLUS_MAGIC: u16 = 0x20B;
+// High bit of a resource directory entry: the name is a string / the entry is a subdirectory
+const RESOURCE_HIGH_BIT: u32 = 0x8000_0000;
+
+// Files whose icons live in PE resources
+const PE_EXTENSIONS: &[&str] = &["exe", "dll", "cpl", "ocx", "scr", "mun"];
+
+// Whether `path` names a file this module can read icons from
+pub fn can_decode(path: &Path) -> bool {
+    let extension = path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    extension == "ico" || PE_EXTENSIONS.contains(&extension.as_str())
+}
+
+// All images of the icon at `icon_index` in `path`. For executables the index follows
+// icon locations: a non-negative value picks the n-th icon group, a negative one the
+// group whose resource ID is its absolute value. .ico files hold a single icon.
+pub fn load_icon(path: &Path, icon_index: i32) -> Result<Vec<RgbaImage>, String> {
+    let data = fs::read(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    let is_ico = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ico"));
+    if is_ico {
+        if icon_index != 0 {
+            return Err(format!("Icon index {} out of range for {}", icon_index, path.display()));
+        }
+        parse_ico(&data)
+    } else {
+        parse_pe_icon(&data, icon_index)
+    }
+}
+
+// Smallest image at least `size` px wide, else the largest one
+pub fn best_image(images: &[RgbaImage], size: u32) -> Option<&RgbaImage> {
+    images.iter()
+        .filter(|image| image.width() >= size)
+        .min_by_key(|image| image.width())
+        .or_else(|| images.iter().max_by_key(|image| image.width()))
+}
+
+// Decode every entry of an .ico file. Entries that fail to decode are skipped as long
+// as one succeeds.
+pub fn parse_ico(data: &[u8]) -> Result<Vec<RgbaImage>, String> {
+    let mut reader = Reader::new(data);
+    let count = read_icon_header(&mut reader)?;
+
+    let mut images = Vec::new();
+    for _ in 0..count {
+        reader.skip(8)?; // width, height, color count, reserved, planes, bit count
+        let size = reader.u32()? as usize;
+        let offset = reader.u32()? as usize;
+
+        if let Some(image) = data.get(offset..offset.saturating_add(size)).and_then(decode_image) {
+            images.push(image);
+        }
+    }
+
+    if images.is_empty() {
+        return Err("Icon file has no readable images".into());
+    }
+    Ok(images)
+}
+
+// Decode the images of one icon group from a PE image
+pub fn parse_pe_icon(data: &[u8], icon_index: i32) -> Result<Vec<RgbaImage>, String> {
+    let pe = PeImage::parse(data)?;
+    let root = pe.resource_root()?;
+
+    let groups = pe.resource_entries(root, RT_GROUP_ICON)?;
+    let group = if icon_index >= 0 {
+        groups.get(icon_index as usize)
+    } else {
+        groups.iter().find(|(id, _)| *id == Some(icon_index.unsigned_abs()))
+    };
+    let &(_, group_offset) = group
+        .ok_or_else(|| format!("Icon index {} out of range ({} icons)", icon_index, groups.len()))?;
+
+    let group_data = pe.first_language_data(group_offset)?;
+    let mut reader = Reader::new(group_data);
+    let count = read_icon_header(&mut reader)?;
+
+    // RT_GROUP_ICON entries match .ico entries, except that a 16-bit RT_ICON resource
+    // ID replaces the file offset
+    let icons = pe.resource_entries(root, RT_ICON)?;
+    let mut images = Vec::new();
+    for _ in 0..count {
+        reader.skip(12)?; // width, height, color count, reserved, planes, bit count, size
+        let id = reader.u16()? as u32;
+
+        let image = icons.iter()
+            .find(|(icon_id, _)| *icon_id == Some(id))
+            .and_then(|&(_, offset)| pe.first_language_data(offset).ok())
+            .and_then(decode_image);
+        if let Some(image) = image {
+            images.push(image);
+        }
+    }
+
+    if images.is_empty() {
+        return Err("Icon group has no readable images".into());
+    }
+    Ok(images)
+}
+
+// ICONDIR / GRPICONDIR header; returns the entry count
+fn read_icon_header(reader: &mut Reader) -> Result<usize, String> {
+    let reserved = reader.u16()?;
+    let kind = reader.u16()?;
+    if reserved != 0 || kind != 1 {
+        return Err("Not an icon".into());
+    }
+    Ok(reader.u16()? as usize)
+}
+
+// One icon image: either an embedded PNG or a headerless BMP (DIB) followed by its mask
+fn decode_image(data: &[u8]) -> Option<RgbaImage> {
+    if data.starts_with(PNG_SIGNATURE) {
+        return image::load_from_memory_with_format(data, ImageFormat::Png)
+            .ok()
+            .map(|image| image.to_rgba8());
+    }
+    decode_dib(data).ok()
+}
+
+// A DIB icon image: BITMAPINFOHEADER, palette, XOR bitmap and 1-bit AND mask, all
+// bottom-up. The header's height covers both bitmaps.
+fn decode_dib(data: &[u8]) -> Result<RgbaImage, String> {
+    let mut reader = Reader::new(data);
+    let header_size = reader.u32()? as usize;
+    let width = reader.u32()? as i32;
+    let height = reader.u32()? as i32 / 2;
+    reader.skip(2)?; // planes
+    let bit_count = reader.u16()? as u32;
+    let compression = reader.u32()?;
+    reader.skip(12)?; // image size, resolution
+    let colors_used = reader.u32()? as usize;
+
+    if width <= 0 || height <= 0 || width > 1024 || height > 1024 {
+        return Err("Unsupported icon dimensions".into());
+    }
+    if compression != 0 {
+        return Err("Compressed icon bitmaps are not supported".into());
+    }
+    if !matches!(bit_count, 1 | 4 | 8 | 24 | 32) {
+        return Err(format!("Unsupported icon bit depth {}", bit_count));
+    }
+    let (width, height) = (width as u32, height as u32);
+
+    let palette_len = if bit_count <= 8 {
+        if colors_used == 0 { 1 << bit_count } else { colors_used }
+    } else {
+        0
+    };
+    reader.seek(header_size)?;
+    let palette = reader.bytes(palette_len * 4)?;
+
+    let xor_stride = row_stride(width, bit_count);
+    let xor = reader.bytes(xor_stride * height as usize)?;
+    let and_stride = row_stride(width, 1);
+    // Some 32-bit images omit the mask
+    let mask = reader.bytes(and_stride * height as usize).ok();
+
+    let mut image = RgbaImage::new(width, height);
+    let mut has_alpha = false;
+    for y in 0..height {
+        let row = &xor[(height - 1 - y) as usize * xor_stride..][..xor_stride];
+        for x in 0..width {
+            let (b, g, r, a) = match bit_count {
+                32 => {
+                    let p = &row[x as usize * 4..][..4];
+                    (p[0], p[1], p[2], p[3])
+                }
+                24 => {
+                    let p = &row[x as usize * 3..][..3];
+                    (p[0], p[1], p[2], 255)
+                }
+                _ => {
+                    let index = palette_index(row, x, bit_count);
+                    let p = palette.get(index * 4..index * 4 + 4).unwrap_or(&[0, 0, 0, 0]);
+                    (p[0], p[1], p[2], 255)
+                }
+            };
+            has_alpha |= bit_count == 32 && a != 0;
+            image.put_pixel(x, y, image::Rgba([r, g, b, a]));
+        }
+    }
+
+    // Without an alpha channel the AND mask decides: set bits are transparent
+    if !has_alpha {
+        for y in 0..height {
+            let row = mask.map(|mask| &mask[(height - 1 - y) as usize * and_stride..][..and_stride]);
+            for x in 0..width {
+                let transparent = row.is_some_and(|row| row[x as usize / 8] & (0x80 >> (x % 8)) != 0);
+                image.get_pixel_mut(x, y)[3] = if transparent { 0 } else { 255 };
+            }
+        }
+    }
+
+    Ok(image)
+}
+
+// Bytes per bitmap row, padded to 32 bits
+fn row_stride(width: u32, bit_count: u32) -> usize {
+    (width as usize * bit_count as usize).div_ceil(32) * 4
+}
+
+fn palette_index(row: &[u8], x: u32, bit_count: u32) -> usize {
+    let bit = x as usize * bit_count as usize;
+    let byte = row[bit / 8];
+    let shift = 8 - bit_count as usize - bit % 8;
+    ((byte >> shift) & ((1u16 << bit_count) - 1) as u8) as usize
+}
+
+struct Section {
+    virtual_address: u32,
+    virtual_size: u32,
+    raw_offset: u32,
+    raw_size: u32,
+}
+
+// Just enough of a PE image to walk its resource directory
+struct PeImage<'a> {
+    data: &'a [u8],
+    sections: Vec<Section>,
+    resource_rva: u32,
+}
+
+impl<'a> PeImage<'a> {
+    fn parse(data: &'a [u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(data);
+        if reader.bytes(2)? != b"MZ" {
+            return Err("Not an executable".into());
+        }
+        reader.seek(0x3C)?;
+        let pe_offset = reader.u32()? as usize;
+
+        reader.seek(pe_offset)?;
+        if reader.bytes(4)? != b"PE\0\0" {
+            return Err("Missing PE signature".into());
+        }
+        reader.skip(2)?; // machine
+        let section_count = reader.u16()? as usize;
+        reader.skip(12)?; // timestamp, symbol table, symbol count
+        let optional_size = reader.u16()? as usize;
+        reader.skip(2)?; // characteristics
+
+        let optional_start = reader.pos;
+        let directories_offset = match reader.u16()? {
+            PE32_MAGIC => 96,
+            PE32_PLUS_MAGIC => 112,
+            magic => return Err(format!("Unknown optional header magic {:#x}", magic)),
+        };
+        reader.seek(optional_start + directories_offset - 4)?;
+        let directory_count = reader.u32()? as usize;
+        if directory_count <= IMAGE_DIRECTORY_ENTRY_RESOURCE {
+            return Err("Executable has no resource directory".into());
+        }
+        reader.skip(IMAGE_DIRECTORY_ENTRY_RESOURCE * 8)?;
+        let resource_rva = reader.u32()?;
+        if resource_rva == 0 {
+            return Err("Executable has no resources".into());
+        }
+
+        reader.seek(optional_start + optional_size)?;
+        let mut sections = Vec::with_capacity(section_count);
+        for _ in 0..section_count {
+            reader.skip(8)?; // name
+            let virtual_size = reader.u32()?;
+            let virtual_address = reader.u32()?;
+            let raw_size = reader.u32()?;
+            let raw_offset = reader.u32()?;
+            reader.skip(16)?; // relocations, line numbers, characteristics
+            sections.push(Section { virtual_address, virtual_size, raw_offset, raw_size });
+        }
+
+        Ok(Self { data, sections, resource_rva })
+    }
+
+    // File offset of a relative virtual address. Section tables come straight from the
+    // file, so every sum is checked rather than trusted.
+    fn offset_of(&self, rva: u32) -> Result<usize, String> {
+        let section = self.sections.iter()
+            .find(|section| {
+                let size = section.virtual_size.max(section.raw_size);
+                rva >= section.virtual_address && rva - section.virtual_address < size
+            })
+            .ok_or_else(|| format!("Address {:#x} is outside every section", rva))?;
+
+        let raw_end = section.raw_offset.checked_add(section.raw_size)
+            .filter(|&end| end as usize <= self.data.len())
+            .ok_or_else(|| "Section data lies outside the file".to_string())?;
+        section.raw_offset.checked_add(rva - section.virtual_address)
+            .filter(|&offset| offset < raw_end)
+            .map(|offset| offset as usize)
+            .ok_or_else(|| format!("Address {:#x} has no data in the file", rva))
+    }
+
+    fn resource_root(&self) -> Result<usize, String> {
+        self.offset_of(self.resource_rva)
+    }
+
+    // (ID, offset) of each entry of a resource directory; named entries have no ID.
+    // Offsets are relative to the start of the resource section.
+    fn directory(&self, root: usize, offset: usize) -> Result<Vec<(Option<u32>, u32)>, String> {
+        let mut reader = Reader::new(self.data);
+        reader.seek(root + offset + 12)?;
+        let count = reader.u16()? as usize + reader.u16()? as usize;
+
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            let name = reader.u32()?;
+            let target = reader.u32()?;
+            let id = (name & RESOURCE_HIGH_BIT == 0).then_some(name);
+            entries.push((id, target));
+        }
+        Ok(entries)
+    }
+
+    // Named, then numbered, resources of type `kind` in directory order, with the offset
+    // of each one's language directory
+    fn resource_entries(&self, root: usize, kind: u32) -> Result<Vec<(Option<u32>, usize)>, String> {
+        let Some(&(_, types)) = self.directory(root, 0)?.iter().find(|(id, _)| *id == Some(kind)) else {
+            return Ok(Vec::new());
+        };
+        if types & RESOURCE_HIGH_BIT == 0 {
+            return Err("Malformed resource directory".into());
+        }
+
+        Ok(self.directory(root, (types & !RESOURCE_HIGH_BIT) as usize)?
+            .into_iter()
+            .filter(|(_, target)| target & RESOURCE_HIGH_BIT != 0)
+            .map(|(id, target)| (id, (target & !RESOURCE_HIGH_BIT) as usize))
+            .collect())
+    }
+
+    // Bytes of the first language variant under a resource's language directory
+    fn first_language_data(&self, languages: usize) -> Result<&'a [u8], String> {
+        let root = self.resource_root()?;
+        let &(_, entry) = self.directory(root, languages)?
+            .first()
+            .ok_or_else(|| "Resource has no data".to_string())?;
+        if entry & RESOURCE_HIGH_BIT != 0 {
+            return Err("Malformed resource directory".into());
+        }
+
+        let mut reader = Reader::new(self.data);
+        reader.seek(root + entry as usize)?;
+        let rva = reader.u32()?;
+        let size = reader.u32()? as usize;
+
+        reader.seek(self.offset_of(rva)?)?;
+        reader.bytes(size)
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn seek(&mut self, pos: usize) -> Result<(), String> {
+        if pos > self.data.len() {
+            return Err("Unexpected end of icon data".into());
+        }
+        self.pos = pos;
+        Ok(())
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| "Unexpected end of icon data".to_string())?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWO_SIZES: &[u8] = include_bytes!("../tests/fixtures/ico/two_sizes.ico");
+    const MASKED: &[u8] = include_bytes!("../tests/fixtures/ico/masked_24bpp.ico");
+    const DLL: &[u8] = include_bytes!("../tests/fixtures/ico/icons.dll");
+    // DOS header, PE signature, COFF header and PE32+ optional header precede it
+    const SECTION_HEADER: usize = 0x40 + 24 + 240;
+    // Resource data entry of RT_ICON 1: .rsrc starts at 0x200 with the root and two
+    // type directories (32 bytes each) and four language directories (24 bytes each)
+    const FIRST_DATA_ENTRY: usize = 0x200 + 3 * 32 + 4 * 24;
+
+    fn widths(images: &[RgbaImage]) -> Vec<u32> {
+        images.iter().map(|image| image.width()).collect()
+    }
+
+    #[test]
+    fn ico_with_dib_and_png_entries() {
+        let images = parse_ico(TWO_SIZES).unwrap();
+        assert_eq!(widths(&images), [16, 32]);
+
+        // 32-bit DIB rows are stored bottom-up
+        let dib = &images[0];
+        assert_eq!(dib.get_pixel(0, 0).0, [0, 0, 0x80, 0xFF]);
+        assert_eq!(dib.get_pixel(3, 15).0, [48, 240, 0x80, 0xFF]);
+
+        let png = &images[1];
+        assert_eq!(png.get_pixel(0, 0).0, [0x20, 0x40, 0xC0, 0xFF]);
+        assert_eq!(png.get_pixel(1, 0).0, [0x20, 0x40, 0xC0, 0x80]);
+
+        assert_eq!(best_image(&images, 24).map(|image| image.width()), Some(32));
+        assert_eq!(best_image(&images, 64).map(|image| image.width()), Some(32));
+    }
+
+    #[test]
+    fn and_mask_sets_transparency_without_alpha() {
+        let images = parse_ico(MASKED).unwrap();
+        assert_eq!(widths(&images), [8]);
+        assert_eq!(images[0].get_pixel(0, 0).0, [0xFF, 0, 0, 0]);
+        assert_eq!(images[0].get_pixel(7, 7).0, [0xFF, 0, 0, 0xFF]);
+    }
+
+    #[test]
+    fn pe_icon_groups_by_index_and_id() {
+        assert_eq!(widths(&parse_pe_icon(DLL, 0).unwrap()), [16, 32]);
+        assert_eq!(widths(&parse_pe_icon(DLL, 1).unwrap()), [32]);
+        assert_eq!(widths(&parse_pe_icon(DLL, -101).unwrap()), [16, 32]);
+        assert_eq!(widths(&parse_pe_icon(DLL, -102).unwrap()), [32]);
+        assert!(parse_pe_icon(DLL, 2).is_err());
+        assert!(parse_pe_icon(DLL, -1).is_err());
+    }
+
+    #[test]
+    fn truncated_and_foreign_data_fail_cleanly() {
+        for len in [0, 5, 40, 200, TWO_SIZES.len() / 2] {
+            let _ = parse_ico(&TWO_SIZES[..len]);
+        }
+        for len in [0, 2, 0x40, SECTION_HEADER, 0x200, 0x240, DLL.len() - 100] {
+            assert!(parse_pe_icon(&DLL[..len], 0).is_err(), "{} bytes", len);
+        }
+        assert!(parse_ico(DLL).is_err());
+        assert!(parse_pe_icon(TWO_SIZES, 0).is_err());
+    }
+
+    #[test]
+    fn crafted_section_tables_are_rejected() {
+        let patch = |patches: &[(usize, u32)]| {
+            let mut data = DLL.to_vec();
+            for &(offset, value) in patches {
+                data[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+            }
+            data
+        };
+        let virtual_size = SECTION_HEADER + 8;
+        let raw_size = SECTION_HEADER + 16;
+        let raw_offset = SECTION_HEADER + 20;
+
+        // An RVA far into a huge virtual section has no data in the file; the icon it
+        // points at is skipped and the rest of the group still loads
+        let beyond_file = patch(&[(virtual_size, u32::MAX), (FIRST_DATA_ENTRY, 0xFFFF_FF00)]);
+        assert_eq!(widths(&parse_pe_icon(&beyond_file, 0).unwrap()), [32]);
+
+        // Offsets that overflow u32 are errors rather than wrapping around
+        let mut image = PeImage::parse(DLL).unwrap();
+        let start = image.sections[0].virtual_address;
+        image.sections[0].raw_offset = u32::MAX - 0x10;
+        assert!(image.offset_of(start + 0x20).is_err());
+
+        // Raw data starting or running past the end of the file
+        assert!(parse_pe_icon(&patch(&[(raw_offset, 0xFFFF_FFF0)]), 0).is_err());
+        assert!(parse_pe_icon(&patch(&[(raw_size, 0x7FFF_FFFF)]), 0).is_err());
+        assert!(parse_pe_icon(&patch(&[(raw_size, u32::MAX)]), 0).is_err());
+    }
+}
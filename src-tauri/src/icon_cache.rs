@@ -0,0 +1,323 @@
+// Extracted app icons, kept as PNG files under <app data>/icon_cache so they survive
+// restarts. Files are named after the hash of their contents, so identical icons (many
+// shortcuts to the same executable) are stored once. index.json maps cache keys to
+// those files and tracks when each entry was last used, for LRU eviction once the
+// cache grows past its size limit.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::app_index::{self, Fingerprint};
+use crate::log_error;
+use crate::settings;
+
+const INDEX_VERSION: u64 = 1;
+const INDEX_FILE: &str = "index.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct IconEntry {
+    hash: String,
+    size: u64,
+    last_used: u64,  // Milliseconds since the Unix epoch
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct IconIndex {
+    version: u64,
+    entries: HashMap<String, IconEntry>,
+}
+
+impl IconIndex {
+    // Size of the PNG files still referenced by an entry
+    fn total_bytes(&self) -> u64 {
+        let mut seen = HashSet::new();
+        self.entries.values()
+            .filter(|entry| seen.insert(entry.hash.as_str()))
+            .map(|entry| entry.size)
+            .sum()
+    }
+}
+
+// Payload returned by the prune_icon_cache command
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct PruneResult {
+    pub entries_removed: usize,
+    pub files_removed: usize,
+    pub bytes_freed: u64,
+    pub total_bytes: u64,
+}
+
+pub struct IconCache {
+    dir: PathBuf,
+    // Loaded on first use. Hits and inserts only change the index in memory; flush
+    // evicts and writes it once per batch of icons.
+    index: Mutex<Option<IconIndex>>,
+    // Set when the in-memory index differs from index.json
+    dirty: AtomicBool,
+}
+
+impl Default for IconCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Cache key for a `size` px icon drawn from `files`: the paths together with their
+// current size and modification time, so replacing or updating any of them yields a
+// new key
+pub fn cache_key(files: &[&str], icon_index: i32, size: u32) -> String {
+    let mut input = format!("{}@{}", icon_index, size);
+    for file in files {
+        let fingerprint = Fingerprint::read(Path::new(file))
+            .map(|fingerprint| format!("{}:{}", fingerprint.modified, fingerprint.size))
+            .unwrap_or_default();
+        input.push_str(&format!("|{}|{}", file.to_lowercase(), fingerprint));
+    }
+    format!("{:x}", md5::compute(input))
+}
+
+impl IconCache {
+    pub fn new() -> Self {
+        let dir = tauri::api::path::app_data_dir(&tauri::Config::default())
+            .unwrap_or_else(|| PathBuf::from("."))
+            .join("icon_cache");
+        Self::in_dir(dir)
+    }
+
+    pub(crate) fn in_dir(dir: PathBuf) -> Self {
+        if !dir.exists() {
+            let _ = fs::create_dir_all(&dir);
+        }
+
+        Self { dir, index: Mutex::new(None), dirty: AtomicBool::new(false) }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<IconIndex>> {
+        let mut index = self.index.lock().unwrap();
+        if index.is_none() {
+            *index = Some(self.read_index());
+        }
+        index
+    }
+
+    fn read_index(&self) -> IconIndex {
+        let path = self.dir.join(INDEX_FILE);
+        let Ok(contents) = fs::read_to_string(&path) else { return IconIndex::default() };
+
+        match serde_json::from_str::<IconIndex>(&contents) {
+            Ok(index) if index.version == INDEX_VERSION => index,
+            Ok(index) => {
+                log_error(&format!("Discarding icon cache index version {}", index.version));
+                IconIndex::default()
+            }
+            Err(e) => {
+                // The PNG files are left for prune to collect
+                log_error(&format!("Icon cache index is corrupt, starting over: {}", e));
+                IconIndex::default()
+            }
+        }
+    }
+
+    fn write_index(&self, index: &mut IconIndex) {
+        index.version = INDEX_VERSION;
+        let result = serde_json::to_vec(index)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| app_index::write_atomic(&self.dir.join(INDEX_FILE), &contents)
+                .map_err(|e| e.to_string()));
+
+        if let Err(e) = result {
+            log_error(&format!("Failed to write icon cache index: {}", e));
+        }
+    }
+
+    fn file_path(&self, hash: &str) -> PathBuf {
+        self.dir.join(format!("{}.png", hash))
+    }
+
+    // Content hash of the PNG cached under `key`, served by the axon-icon:// protocol
+    pub fn get(&self, key: &str) -> Option<String> {
+        let mut guard = self.lock();
+        let index = guard.as_mut()?;
+        let entry = index.entries.get_mut(key)?;
+
+        let found = if self.file_path(&entry.hash).is_file() {
+            entry.last_used = now_millis();
+            Some(entry.hash.clone())
+        } else {
+            // The file was deleted behind our back
+            index.entries.remove(key);
+            None
+        };
+        self.dirty.store(true, Ordering::SeqCst);
+        found
+    }
+
+    // Store `png` under `key` and return its content hash. The entry only reaches
+    // index.json with the next flush.
+    pub fn insert(&self, key: &str, png: &[u8]) -> Result<String, String> {
+        let hash = format!("{:x}", md5::compute(png));
+        let path = self.file_path(&hash);
+
+        if !path.exists() {
+            app_index::write_atomic(&path, png)
+                .map_err(|e| format!("Failed to write cached icon {}: {}", path.display(), e))?;
+        }
+
+        let mut guard = self.lock();
+        if let Some(index) = guard.as_mut() {
+            index.entries.insert(key.to_string(), IconEntry {
+                hash: hash.clone(),
+                size: png.len() as u64,
+                last_used: now_millis(),
+            });
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+        Ok(hash)
+    }
+
+    // Evict down to the configured size limit and write the index, if anything
+    // changed since the last flush
+    pub fn flush(&self) {
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        let max_bytes = settings::load_or_default().icon_cache_max_bytes;
+        let mut guard = self.lock();
+        if let Some(index) = guard.as_mut() {
+            self.evict(index, max_bytes);
+            self.write_index(index);
+        }
+    }
+
+    // PNG bytes for a content hash handed out by get or insert
+    pub fn read(&self, hash: &str) -> Option<Vec<u8>> {
+        if !is_hash(hash) {
+            return None;
+        }
+        fs::read(self.file_path(hash)).ok()
+    }
+
+    // Drop entries whose files are gone, evict down to `max_bytes` (the configured
+    // limit when None) and delete PNG files no entry refers to
+    pub fn prune(&self, max_bytes: Option<u64>) -> Result<PruneResult, String> {
+        let max_bytes = max_bytes.unwrap_or_else(|| settings::load_or_default().icon_cache_max_bytes);
+        let mut guard = self.lock();
+        let Some(index) = guard.as_mut() else { return Ok(PruneResult::default()) };
+
+        let before = index.entries.len();
+        index.entries.retain(|_, entry| self.file_path(&entry.hash).exists());
+        let mut result = self.evict(index, max_bytes);
+        result.entries_removed = before - index.entries.len();
+
+        let referenced: HashSet<PathBuf> = index.entries.values()
+            .map(|entry| self.file_path(&entry.hash))
+            .collect();
+        let entries = fs::read_dir(&self.dir)
+            .map_err(|e| format!("Failed to read icon cache: {}", e))?;
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "png") && !referenced.contains(&path) {
+                let size = entry.metadata().map(|metadata| metadata.len()).unwrap_or_default();
+                if fs::remove_file(&path).is_ok() {
+                    result.files_removed += 1;
+                    result.bytes_freed += size;
+                }
+            }
+        }
+
+        self.write_index(index);
+        self.dirty.store(false, Ordering::SeqCst);
+        result.total_bytes = index.total_bytes();
+        Ok(result)
+    }
+
+    // Remove least recently used entries until the referenced files fit in `max_bytes`
+    fn evict(&self, index: &mut IconIndex, max_bytes: u64) -> PruneResult {
+        let mut result = PruneResult::default();
+        let mut total = index.total_bytes();
+        if total <= max_bytes {
+            return result;
+        }
+
+        let mut by_age: Vec<(String, u64)> = index.entries.iter()
+            .map(|(key, entry)| (key.clone(), entry.last_used))
+            .collect();
+        by_age.sort_by_key(|(_, last_used)| *last_used);
+
+        for (key, _) in by_age {
+            if total <= max_bytes {
+                break;
+            }
+            let Some(entry) = index.entries.remove(&key) else { continue };
+            result.entries_removed += 1;
+
+            // Shared files go once their last entry does
+            if !index.entries.values().any(|other| other.hash == entry.hash) {
+                total = total.saturating_sub(entry.size);
+                if fs::remove_file(self.file_path(&entry.hash)).is_ok() {
+                    result.files_removed += 1;
+                    result.bytes_freed += entry.size;
+                }
+            }
+        }
+
+        result
+    }
+}
+
+// Cache files are named by an md5 digest; anything else can't name one of them and is
+// rejected before it reaches the file system
+pub fn is_hash(name: &str) -> bool {
+    name.len() == 32 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|since| since.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_used(cache: &IconCache, key: &str) -> Option<u64> {
+        cache.lock().as_ref()?.entries.get(key).map(|entry| entry.last_used)
+    }
+
+    #[test]
+    fn index_is_written_on_flush_with_hit_times() {
+        let dir = std::env::temp_dir().join(format!("axon-icon-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let cache = IconCache::in_dir(dir.clone());
+
+        let hash = cache.insert("a", b"first png").unwrap();
+        cache.insert("b", b"second png").unwrap();
+        assert!(!dir.join(INDEX_FILE).exists());
+        cache.flush();
+        assert!(dir.join(INDEX_FILE).exists());
+
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(cache.get("a"), Some(hash.clone()));
+        let hit = last_used(&cache, "a").unwrap();
+        cache.flush();
+
+        // A new instance sees the hit, so LRU order survives a restart
+        let reopened = IconCache::in_dir(dir.clone());
+        assert_eq!(last_used(&reopened, "a"), Some(hit));
+        assert!(last_used(&reopened, "b").unwrap() < hit);
+        assert_eq!(reopened.read(&hash).as_deref(), Some(&b"first png"[..]));
+
+        let result = reopened.prune(Some(12)).unwrap();
+        assert_eq!(result.entries_removed, 1);
+        assert_eq!(reopened.get("b"), None);
+        assert_eq!(reopened.get("a"), Some(hash));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,209 @@
+// The axon-icon:// protocol. Commands hand icons to the frontend as URLs naming a PNG
+// by hash instead of inlining the bytes as base64 data URLs, which kept IPC messages
+// and the persisted store large. The webview fetches the bytes from the handler below.
+//
+// Extracted icons are named by the hash of their contents, so a URL always means the
+// same image and may be cached indefinitely. Custom icons are named after the app or
+// folder path they replace and carry a `v` parameter with the content hash instead,
+// so replacing one yields a new URL.
+
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use image::{imageops::FilterType, ImageFormat};
+use tauri::http::{Request, Response, ResponseBuilder};
+use tauri::{AppHandle, Manager};
+use url::Url;
+use crate::icon_cache::{self, IconCache};
+
+pub const SCHEME: &str = "axon-icon";
+
+// Directories under the app data dir that hold user-chosen icons
+const CUSTOM_ICON_DIRS: &[&str] = &["custom_icons", "custom_folder_icons"];
+const CUSTOM_ICON_SIZE: u32 = 128;
+// Smallest size the handler will scale down to
+const MIN_ICON_SIZE: u32 = 16;
+
+const CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+// URL of a cached icon, rendered at `size` px
+pub fn icon_url(hash: &str, size: u32) -> String {
+    // WebView2 only routes custom schemes through http(s)://<scheme>.localhost
+    if cfg!(windows) {
+        format!("https://{}.localhost/{}?size={}", SCHEME, hash, size)
+    } else {
+        format!("{}://{}?size={}", SCHEME, hash, size)
+    }
+}
+
+// URL of the custom icon stored for `path` with the contents `png`
+pub fn custom_icon_url(path: &str, png: &[u8]) -> String {
+    format!(
+        "{}&v={:x}",
+        icon_url(&custom_icon_name(path), CUSTOM_ICON_SIZE),
+        md5::compute(png)
+    )
+}
+
+// File stem of the custom icon for an app or folder path
+pub fn custom_icon_name(path: &str) -> String {
+    format!("{:x}", md5::compute(path))
+}
+
+// Handler registered with register_uri_scheme_protocol
+pub fn handle(app: &AppHandle, request: &Request) -> Result<Response, Box<dyn Error>> {
+    let app_data_dir = tauri::api::path::app_data_dir(&tauri::Config::default());
+    respond(&app.state::<IconCache>(), app_data_dir.as_deref(), request.uri())
+}
+
+// The icon named by `uri`, from the icon cache or the custom icons under `app_data_dir`
+fn respond(icons: &IconCache, app_data_dir: Option<&Path>, uri: &str) -> Result<Response, Box<dyn Error>> {
+    let url = Url::parse(uri)?;
+    let Some(hash) = icon_hash(&url) else { return not_found() };
+
+    let custom_icon = || app_data_dir.and_then(|dir| read_custom_icon(dir, &hash));
+    let Some(png) = icons.read(&hash).or_else(custom_icon) else { return not_found() };
+
+    let size = url.query_pairs()
+        .find(|(name, _)| name == "size")
+        .and_then(|(_, value)| value.parse::<u32>().ok());
+    let png = match size {
+        Some(size) => scale_down(png, size),
+        None => png,
+    };
+
+    ResponseBuilder::new()
+        .mimetype("image/png")
+        .header("Cache-Control", CACHE_CONTROL)
+        .header("Access-Control-Allow-Origin", "*")
+        .body(png)
+}
+
+// The hash is the host of axon-icon://<hash>, or the first path segment when the
+// webview rewrote the URL to https://axon-icon.localhost/<hash>
+fn icon_hash(url: &Url) -> Option<String> {
+    let host = url.host_str().unwrap_or_default();
+    let name = if host.is_empty() || host == "localhost" || host.ends_with(".localhost") {
+        url.path_segments()?.next()?
+    } else {
+        host
+    };
+
+    let name = name.to_ascii_lowercase();
+    icon_cache::is_hash(&name).then_some(name)
+}
+
+fn read_custom_icon(app_data_dir: &Path, name: &str) -> Option<Vec<u8>> {
+    CUSTOM_ICON_DIRS.iter()
+        .find_map(|dir| fs::read(app_data_dir.join(dir).join(format!("{}.png", name))).ok())
+}
+
+// Re-encode `png` at `size` px when it is larger than that; otherwise, or if it can't
+// be decoded, serve it as stored and let the webview scale it
+fn scale_down(png: Vec<u8>, size: u32) -> Vec<u8> {
+    let size = size.max(MIN_ICON_SIZE);
+    let Ok(img) = image::load_from_memory_with_format(&png, ImageFormat::Png) else { return png };
+    if img.width() <= size && img.height() <= size {
+        return png;
+    }
+
+    let mut scaled = Vec::new();
+    match img.resize(size, size, FilterType::Lanczos3)
+        .write_to(&mut std::io::Cursor::new(&mut scaled), ImageFormat::Png)
+    {
+        Ok(()) => scaled,
+        Err(_) => png,
+    }
+}
+
+fn not_found() -> Result<Response, Box<dyn Error>> {
+    ResponseBuilder::new()
+        .status(404)
+        .mimetype("text/plain")
+        .body(Vec::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbaImage;
+    use super::*;
+    use crate::icons::encode_png;
+
+    const HASH: &str = "0123456789abcdef0123456789abcdef";
+
+    fn png(size: u32) -> Vec<u8> {
+        encode_png(&RgbaImage::new(size, size), size).unwrap()
+    }
+
+    fn dimensions(png: &[u8]) -> (u32, u32) {
+        let image = image::load_from_memory(png).unwrap();
+        (image.width(), image.height())
+    }
+
+    fn hash_of(url: &str) -> Option<String> {
+        icon_hash(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn hash_from_either_url_form() {
+        assert_eq!(hash_of(&format!("axon-icon://{}?size=32", HASH)).as_deref(), Some(HASH));
+        assert_eq!(hash_of(&format!("https://axon-icon.localhost/{}?size=32", HASH)).as_deref(), Some(HASH));
+        assert_eq!(hash_of(&format!("axon-icon://{}", HASH.to_uppercase())).as_deref(), Some(HASH));
+        // Anything but a digest could name a file outside the cache
+        assert_eq!(hash_of("axon-icon://not-a-hash"), None);
+        assert_eq!(hash_of("https://axon-icon.localhost/..%2Findex.json"), None);
+        assert_eq!(hash_of("https://axon-icon.localhost/"), None);
+    }
+
+    #[test]
+    fn urls_round_trip() {
+        let url = icon_url(HASH, 48);
+        assert_eq!(hash_of(&url).as_deref(), Some(HASH));
+        assert!(url.ends_with("?size=48"));
+
+        // A custom icon keeps its name but changes its URL with its contents
+        let path = "C:\\Start Menu\\Programs\\Editor.lnk";
+        let first = custom_icon_url(path, &png(16));
+        let second = custom_icon_url(path, &png(32));
+        assert_ne!(first, second);
+        assert_eq!(hash_of(&first), Some(custom_icon_name(path)));
+        assert_eq!(hash_of(&second), Some(custom_icon_name(path)));
+        assert!(first.ends_with(&format!("&v={:x}", md5::compute(png(16)))));
+    }
+
+    #[test]
+    fn only_larger_icons_are_scaled_down() {
+        assert_eq!(dimensions(&scale_down(png(64), 32)), (32, 32));
+        assert_eq!(dimensions(&scale_down(png(64), 1)), (MIN_ICON_SIZE, MIN_ICON_SIZE));
+        assert_eq!(scale_down(png(24), 32), png(24));
+        assert_eq!(scale_down(b"not a png".to_vec(), 32), b"not a png");
+    }
+
+    #[test]
+    fn responses() {
+        let dir = std::env::temp_dir().join(format!("axon-icon-protocol-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let icons = IconCache::in_dir(dir.join("icon_cache"));
+        let hash = icons.insert("key", &png(64)).unwrap();
+        let path = "/opt/editor/editor.desktop";
+        fs::create_dir_all(dir.join("custom_icons")).unwrap();
+        fs::write(dir.join("custom_icons").join(format!("{}.png", custom_icon_name(path))), png(128)).unwrap();
+
+        let cached = respond(&icons, Some(&dir), &icon_url(&hash, 32)).unwrap();
+        let unscaled = respond(&icons, Some(&dir), &format!("axon-icon://{}", hash)).unwrap();
+        let custom = respond(&icons, Some(&dir), &custom_icon_url(path, &png(128))).unwrap();
+        let missing = respond(&icons, Some(&dir), &icon_url(HASH, 32)).unwrap();
+        let invalid = respond(&icons, Some(&dir), "axon-icon://index.json").unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(cached.status().as_u16(), 200);
+        assert_eq!(cached.headers().get("cache-control").unwrap(), CACHE_CONTROL);
+        assert_eq!(dimensions(cached.body()), (32, 32));
+        assert_eq!(dimensions(unscaled.body()), (64, 64));
+        // The v parameter only busts the webview's cache; the handler ignores it
+        assert_eq!(custom.status().as_u16(), 200);
+        assert_eq!(dimensions(custom.body()), (CUSTOM_ICON_SIZE, CUSTOM_ICON_SIZE));
+        assert_eq!(missing.status().as_u16(), 404);
+        assert_eq!(invalid.status().as_u16(), 404);
+    }
+}
@@ -0,0 +1,476 @@
+// freedesktop.org Icon Theme lookup: resolves the Icon key of a desktop entry to a
+// file in the user's icon theme, the themes it inherits from or hicolor, following
+// the Icon Theme Specification, and rasterizes the result.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use image::RgbaImage;
+use crate::desktop_entry;
+
+const FALLBACK_THEME: &str = "hicolor";
+// Checked in this order within each directory. XPM icons are skipped since nothing
+// here can decode them.
+const EXTENSIONS: &[&str] = &["png", "svg"];
+
+// Themes under the user's and the system's base directories
+static THEMES: OnceLock<Themes> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DirectoryType {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+// A subdirectory of a theme as described by its index.theme section
+#[derive(Debug, Clone)]
+struct ThemeDirectory {
+    path: String,
+    size: u32,
+    scale: u32,
+    kind: DirectoryType,
+    min_size: u32,
+    max_size: u32,
+    threshold: u32,
+}
+
+impl ThemeDirectory {
+    fn parse(path: &str, keys: &HashMap<String, String>) -> Option<Self> {
+        let number = |key: &str| keys.get(key).and_then(|value| value.trim().parse::<u32>().ok());
+        let size = number("Size")?;
+        let kind = match keys.get("Type").map(|value| value.trim()) {
+            Some("Fixed") => DirectoryType::Fixed,
+            Some("Scalable") => DirectoryType::Scalable,
+            _ => DirectoryType::Threshold,
+        };
+
+        Some(Self {
+            path: path.to_string(),
+            size,
+            scale: number("Scale").unwrap_or(1).max(1),
+            kind,
+            min_size: number("MinSize").unwrap_or(size),
+            max_size: number("MaxSize").unwrap_or(size),
+            threshold: number("Threshold").unwrap_or(2),
+        })
+    }
+
+    // DirectoryMatchesSize from the specification
+    fn matches_size(&self, size: u32, scale: u32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            DirectoryType::Fixed => self.size == size,
+            DirectoryType::Scalable => (self.min_size..=self.max_size).contains(&size),
+            DirectoryType::Threshold => {
+                (self.size.saturating_sub(self.threshold)..=self.size + self.threshold).contains(&size)
+            }
+        }
+    }
+
+    // DirectorySizeDistance from the specification, in device pixels
+    fn size_distance(&self, size: u32, scale: u32) -> u64 {
+        let wanted = (size * scale) as i64;
+        let scaled = |value: u32| (value * self.scale) as i64;
+
+        let distance = match self.kind {
+            DirectoryType::Fixed => (scaled(self.size) - wanted).abs(),
+            DirectoryType::Scalable | DirectoryType::Threshold => {
+                let (low, high) = match self.kind {
+                    DirectoryType::Scalable => (scaled(self.min_size), scaled(self.max_size)),
+                    _ => (
+                        scaled(self.size.saturating_sub(self.threshold)),
+                        scaled(self.size + self.threshold),
+                    ),
+                };
+                if wanted < low {
+                    scaled(self.min_size) - wanted
+                } else if wanted > high {
+                    wanted - scaled(self.max_size)
+                } else {
+                    0
+                }
+            }
+        };
+        distance.unsigned_abs()
+    }
+}
+
+#[derive(Debug)]
+struct Theme {
+    // The theme's directory under each base directory that has one
+    roots: Vec<PathBuf>,
+    directories: Vec<ThemeDirectory>,
+    inherits: Vec<String>,
+}
+
+impl Theme {
+    fn load(name: &str, base_dirs: &[PathBuf]) -> Option<Self> {
+        let roots: Vec<PathBuf> = base_dirs.iter()
+            .map(|base| base.join(name))
+            .filter(|root| root.is_dir())
+            .collect();
+        let index = roots.iter().find_map(|root| fs::read_to_string(root.join("index.theme")).ok())?;
+        let sections = parse_ini(&index);
+        let header = sections.get("Icon Theme")?;
+
+        let list = |key: &str| -> Vec<String> {
+            header.get(key)
+                .map(|value| value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect())
+                .unwrap_or_default()
+        };
+
+        let directories = list("Directories").into_iter()
+            .chain(list("ScaledDirectories"))
+            .filter_map(|dir| sections.get(&dir).and_then(|keys| ThemeDirectory::parse(&dir, keys)))
+            .collect();
+
+        Some(Self { roots, directories, inherits: list("Inherits") })
+    }
+
+    // LookupIcon from the specification: an exact size match if there is one, else
+    // the closest size
+    fn lookup(&self, name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        let mut closest: Option<(u64, PathBuf)> = None;
+
+        for dir in &self.directories {
+            for root in &self.roots {
+                for ext in EXTENSIONS {
+                    let path = root.join(&dir.path).join(format!("{}.{}", name, ext));
+                    if !path.is_file() {
+                        continue;
+                    }
+                    if dir.matches_size(size, scale) {
+                        return Some(path);
+                    }
+                    let distance = dir.size_distance(size, scale);
+                    if closest.as_ref().is_none_or(|(best, _)| distance < *best) {
+                        closest = Some((distance, path));
+                    }
+                }
+            }
+        }
+
+        closest.map(|(_, path)| path)
+    }
+}
+
+// The themes found under a list of base directories, parsed on first use
+struct Themes {
+    base_dirs: Vec<PathBuf>,
+    // Parsed themes by name; None for names with no index.theme
+    loaded: Mutex<HashMap<String, Option<Arc<Theme>>>>,
+}
+
+impl Themes {
+    fn new(base_dirs: Vec<PathBuf>) -> Self {
+        Self { base_dirs, loaded: Mutex::new(HashMap::new()) }
+    }
+
+    fn theme(&self, name: &str) -> Option<Arc<Theme>> {
+        let mut loaded = self.loaded.lock().unwrap();
+        loaded.entry(name.to_string())
+            .or_insert_with(|| Theme::load(name, &self.base_dirs).map(Arc::new))
+            .clone()
+    }
+
+    // find_icon with `current` as the user's theme
+    fn find_icon(&self, current: Option<&str>, name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+        let path = Path::new(name);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_path_buf());
+        }
+
+        // Icon keys aren't supposed to carry an extension, but some do
+        let name = ["png", "svg", "xpm"].iter()
+            .find_map(|ext| name.strip_suffix(&format!(".{}", ext)))
+            .unwrap_or(name);
+
+        let mut visited = HashSet::new();
+        current
+            .and_then(|theme| self.find_in_theme(theme, name, size, scale, &mut visited))
+            .or_else(|| self.find_in_theme(FALLBACK_THEME, name, size, scale, &mut visited))
+            .or_else(|| self.find_fallback(name))
+    }
+
+    // FindIconHelper from the specification; hicolor is left for the caller so it is
+    // searched after every other ancestor
+    fn find_in_theme(&self, name: &str, icon: &str, size: u32, scale: u32, visited: &mut HashSet<String>) -> Option<PathBuf> {
+        if !visited.insert(name.to_string()) {
+            return None;
+        }
+        let theme = self.theme(name)?;
+
+        theme.lookup(icon, size, scale).or_else(|| {
+            theme.inherits.iter()
+                .filter(|parent| parent.as_str() != FALLBACK_THEME)
+                .find_map(|parent| self.find_in_theme(parent, icon, size, scale, visited))
+        })
+    }
+
+    // LookupFallbackIcon: unthemed icons directly in a base directory
+    fn find_fallback(&self, name: &str) -> Option<PathBuf> {
+        self.base_dirs.iter()
+            .flat_map(|base| EXTENSIONS.iter().map(move |ext| base.join(format!("{}.{}", name, ext))))
+            .find(|path| path.is_file())
+    }
+}
+
+// File for the icon `name` at `size` px and `scale`. Absolute paths are used as they
+// are; names are looked up in the current theme and its parents, then hicolor, then
+// directly in the base directories.
+pub fn find_icon(name: &str, size: u32, scale: u32) -> Option<PathBuf> {
+    let themes = THEMES.get_or_init(|| Themes::new(base_dirs()));
+    // Absolute paths don't need the theme name from the settings files
+    if Path::new(name).is_absolute() {
+        return themes.find_icon(None, name, size, scale);
+    }
+    themes.find_icon(current_theme().as_deref(), name, size, scale)
+}
+
+// $HOME/.icons, each $XDG_DATA_DIRS/icons (with $XDG_DATA_HOME first) and /usr/share/pixmaps
+fn base_dirs() -> Vec<PathBuf> {
+    let home_icons = std::env::var("HOME").ok().map(|home| PathBuf::from(home).join(".icons"));
+
+    home_icons.into_iter()
+        .chain(desktop_entry::data_dirs().into_iter().map(|dir| dir.join("icons")))
+        .chain(std::iter::once(PathBuf::from("/usr/share/pixmaps")))
+        .collect()
+}
+
+// Icon theme chosen in the GTK or KDE settings
+fn current_theme() -> Option<String> {
+    let config_home = std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var("HOME").ok().map(|home| PathBuf::from(home).join(".config")))?;
+
+    let sources = [
+        ("gtk-4.0/settings.ini", "Settings", "gtk-icon-theme-name"),
+        ("gtk-3.0/settings.ini", "Settings", "gtk-icon-theme-name"),
+        ("kdeglobals", "Icons", "Theme"),
+    ];
+
+    sources.iter().find_map(|(file, section, key)| {
+        let content = fs::read_to_string(config_home.join(file)).ok()?;
+        parse_ini(&content)
+            .get(*section)?
+            .get(*key)
+            .map(|value| value.trim().trim_matches('"').to_string())
+            .filter(|value| !value.is_empty())
+    })
+}
+
+// Groups of key=value lines; later duplicate keys win
+fn parse_ini(content: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            current = Some(name.to_string());
+            sections.entry(name.to_string()).or_default();
+            continue;
+        }
+        if let (Some(section), Some((key, value))) = (&current, line.split_once('=')) {
+            sections.entry(section.clone())
+                .or_default()
+                .insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+
+    sections
+}
+
+// Whether load_image can read `path`
+pub fn can_load(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| EXTENSIONS.contains(&ext.as_str()))
+}
+
+// Read an icon file, rasterizing SVGs at `size` px
+pub fn load_image(path: &Path, size: u32) -> Result<RgbaImage, String> {
+    let is_svg = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("svg"));
+    if !is_svg {
+        return image::open(path)
+            .map(|image| image.to_rgba8())
+            .map_err(|e| format!("Failed to read icon {}: {}", path.display(), e));
+    }
+
+    let data = fs::read(path)
+        .map_err(|e| format!("Failed to read icon {}: {}", path.display(), e))?;
+    render_svg(&data, size)
+        .map_err(|e| format!("Failed to render icon {}: {}", path.display(), e))
+}
+
+// Render an SVG into a `size` x `size` image, scaled to fit and centred
+fn render_svg(data: &[u8], size: u32) -> Result<RgbaImage, String> {
+    let tree = resvg::usvg::Tree::from_data(data, &resvg::usvg::Options::default())
+        .map_err(|e| e.to_string())?;
+
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size, size)
+        .ok_or_else(|| "Invalid icon size".to_string())?;
+    let view = tree.size();
+    let scale = (size as f32 / view.width()).min(size as f32 / view.height());
+    let transform = resvg::tiny_skia::Transform::from_scale(scale, scale).post_translate(
+        (size as f32 - view.width() * scale) / 2.0,
+        (size as f32 - view.height() * scale) / 2.0,
+    );
+    resvg::render(&tree, transform, &mut pixmap.as_mut());
+
+    // tiny-skia works in premultiplied alpha
+    let pixels = pixmap.pixels().iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+    RgbaImage::from_raw(size, size, pixels).ok_or_else(|| "Failed to render icon".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Fresh base directory under the system temp dir holding the given files
+    fn temp_base(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let base = std::env::temp_dir().join(format!("axon-icon-theme-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        for (path, contents) in files {
+            let path = base.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        base
+    }
+
+    fn directory(keys: &[(&str, &str)]) -> ThemeDirectory {
+        let keys = keys.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+        ThemeDirectory::parse("apps", &keys).unwrap()
+    }
+
+    const CUSTOM_INDEX: &str = "\
+# Comments and unknown keys are skipped
+[Icon Theme]
+Name=Custom
+Inherits=Parent, hicolor
+Directories=16x16/apps,48x48/apps,scalable/apps,undescribed/apps
+ScaledDirectories=16x16@2/apps
+
+[16x16/apps]
+Size=16
+Type=Fixed
+
+[16x16@2/apps]
+Size=16
+Scale=2
+Type=Fixed
+
+[48x48/apps]
+Size=48
+Type=Threshold
+Threshold=4
+
+[scalable/apps]
+Size=128
+Type=Scalable
+MinSize=64
+MaxSize=256
+";
+
+    #[test]
+    fn index_theme_sections() {
+        let base = temp_base("index", &[("Custom/index.theme", CUSTOM_INDEX)]);
+        let theme = Theme::load("Custom", std::slice::from_ref(&base));
+        let missing = Theme::load("Missing", std::slice::from_ref(&base));
+        let _ = fs::remove_dir_all(&base);
+
+        let theme = theme.unwrap();
+        assert!(missing.is_none());
+        assert_eq!(theme.inherits, ["Parent", "hicolor"]);
+        // Directories without a section of their own are left out
+        let paths: Vec<_> = theme.directories.iter().map(|dir| dir.path.as_str()).collect();
+        assert_eq!(paths, ["16x16/apps", "48x48/apps", "scalable/apps", "16x16@2/apps"]);
+        let kinds: Vec<_> = theme.directories.iter().map(|dir| (dir.kind, dir.size, dir.scale)).collect();
+        assert_eq!(kinds, [
+            (DirectoryType::Fixed, 16, 1),
+            (DirectoryType::Threshold, 48, 1),
+            (DirectoryType::Scalable, 128, 1),
+            (DirectoryType::Fixed, 16, 2),
+        ]);
+        assert_eq!((theme.directories[2].min_size, theme.directories[2].max_size), (64, 256));
+    }
+
+    #[test]
+    fn sizes_by_directory_type() {
+        let fixed = directory(&[("Size", "16"), ("Type", "Fixed")]);
+        assert!(fixed.matches_size(16, 1));
+        assert!(!fixed.matches_size(17, 1));
+        assert!(!fixed.matches_size(16, 2));
+        assert_eq!(fixed.size_distance(24, 1), 8);
+        assert_eq!(fixed.size_distance(8, 2), 0);
+
+        let scalable = directory(&[("Size", "128"), ("Type", "Scalable"), ("MinSize", "64"), ("MaxSize", "256")]);
+        assert!(scalable.matches_size(64, 1) && scalable.matches_size(256, 1));
+        assert!(!scalable.matches_size(32, 1));
+        assert_eq!(scalable.size_distance(32, 1), 32);
+        assert_eq!(scalable.size_distance(300, 1), 44);
+        assert_eq!(scalable.size_distance(100, 1), 0);
+
+        // Threshold is the default type, with a threshold of 2
+        let threshold = directory(&[("Size", "48")]);
+        assert_eq!(threshold.kind, DirectoryType::Threshold);
+        assert!(threshold.matches_size(46, 1) && threshold.matches_size(50, 1));
+        assert!(!threshold.matches_size(45, 1));
+        assert_eq!(threshold.size_distance(40, 1), 8);
+        assert_eq!(threshold.size_distance(56, 1), 8);
+
+        assert!(ThemeDirectory::parse("apps", &HashMap::new()).is_none());
+    }
+
+    #[test]
+    fn lookup_follows_inherits_then_hicolor() {
+        let base = temp_base("lookup", &[
+            ("Custom/index.theme", CUSTOM_INDEX),
+            ("Custom/16x16/apps/editor.png", ""),
+            ("Custom/scalable/apps/editor.svg", ""),
+            // Parent inherits back from Custom, which must not loop
+            ("Parent/index.theme", "[Icon Theme]\nInherits=Custom\nDirectories=32x32/apps\n[32x32/apps]\nSize=32\nType=Fixed\n"),
+            ("Parent/32x32/apps/viewer.png", ""),
+            ("hicolor/index.theme", "[Icon Theme]\nDirectories=48x48/apps\n[48x48/apps]\nSize=48\n"),
+            ("hicolor/48x48/apps/viewer.png", ""),
+            ("hicolor/48x48/apps/terminal.png", ""),
+            ("unthemed.png", ""),
+        ]);
+        let themes = Themes::new(vec![base.clone()]);
+        let find = |name: &str, size: u32| themes.find_icon(Some("Custom"), name, size, 1);
+
+        assert_eq!(find("editor", 16), Some(base.join("Custom/16x16/apps/editor.png")));
+        assert_eq!(find("editor.png", 16), Some(base.join("Custom/16x16/apps/editor.png")));
+        assert_eq!(find("editor", 128), Some(base.join("Custom/scalable/apps/editor.svg")));
+        // No exact match: the closest size wins
+        assert_eq!(find("editor", 24), Some(base.join("Custom/16x16/apps/editor.png")));
+        // Parent before hicolor, even though hicolor's size matches better
+        assert_eq!(find("viewer", 48), Some(base.join("Parent/32x32/apps/viewer.png")));
+        assert_eq!(find("terminal", 48), Some(base.join("hicolor/48x48/apps/terminal.png")));
+        assert_eq!(find("unthemed", 48), Some(base.join("unthemed.png")));
+        assert_eq!(find("missing", 48), None);
+        // Without a current theme, straight to hicolor
+        assert_eq!(themes.find_icon(None, "editor", 16, 1), None);
+
+        // Absolute Icon= paths are used as they are, if they exist
+        let absolute = base.join("Custom/16x16/apps/editor.png");
+        assert_eq!(themes.find_icon(None, absolute.to_str().unwrap(), 48, 1), Some(absolute.clone()));
+        assert_eq!(themes.find_icon(None, base.join("gone.png").to_str().unwrap(), 48, 1), None);
+        let _ = fs::remove_dir_all(&base);
+    }
+}
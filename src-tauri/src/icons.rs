@@ -0,0 +1,340 @@
+// Icon extraction for discovered apps. Single lookups and batches share the same
+// resolution (source icon location, then the persistent icon cache, then the icon
+// decoder or the shell).
+
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use image::imageops::{self, FilterType};
+use image::{ImageEncoder, RgbaImage};
+use serde::Serialize;
+#[cfg(windows)]
+use windows::Win32::System::Com::{CoInitializeEx, CoUninitialize, COINIT_APARTMENTTHREADED};
+use crate::app_manager::AppManager;
+use crate::discovery::AppInfo;
+use crate::ico;
+#[cfg(target_os = "linux")]
+use crate::icon_theme;
+use crate::icon_cache::{self, IconCache};
+use crate::icon_protocol;
+use crate::internet_shortcut;
+use crate::log_error;
+#[cfg(windows)]
+use crate::shell_icons;
+use crate::sources;
+
+pub const DEFAULT_ICON_SIZE: u32 = 128;
+const MAX_ICON_SIZE: u32 = 256;
+// Upper bound on concurrent extractions; each worker holds its own COM apartment
+const MAX_ICON_WORKERS: usize = 4;
+
+// Payload of the icons://loaded event, one per requested path
+#[derive(Serialize, Debug, Clone)]
+pub struct IconResult {
+    pub path: String,
+    pub icon: Option<String>,
+    pub error: Option<String>,
+}
+
+// Returned by get_app_icons once every path has been reported
+#[derive(Serialize, Debug, Clone, Default)]
+pub struct IconBatchSummary {
+    pub loaded: usize,
+    pub failed: usize,
+}
+
+// Icon for a launcher entry as an axon-icon:// URL
+pub fn load_icon(path: &str, size: u32, manager: &AppManager, icons: &IconCache) -> Result<String, String> {
+    let result = cached_icon_url(path, size, manager, icons);
+    icons.flush();
+    result
+}
+
+// load_icon without writing the cache index, for batches that flush once at the end
+fn cached_icon_url(path: &str, size: u32, manager: &AppManager, icons: &IconCache) -> Result<String, String> {
+    let size = size.clamp(16, MAX_ICON_SIZE);
+
+    // Let the entry's source decide which file the icon comes from
+    let app = manager.find(path);
+    let icon_path = app.as_ref()
+        .and_then(|app| sources::source_for(app).and_then(|source| source.resolve_icon(app)))
+        .unwrap_or_else(|| path.to_string());
+
+    let location = icon_location(app.as_ref(), &icon_path);
+
+    // Changes to the shortcut, its icon location or its target all produce a new key
+    let mut key_files = vec![icon_path.as_str()];
+    if let Some(app) = &app {
+        key_files.extend(app.icon_path.as_deref());
+        key_files.extend(app.target.as_deref());
+    }
+    key_files.extend(location.as_ref().map(|(file, _)| file.as_str()));
+    let icon_index = location.as_ref()
+        .map(|(_, index)| *index)
+        .unwrap_or_else(|| app.as_ref().map_or(0, |app| app.icon_index));
+    let key = icon_cache::cache_key(&key_files, icon_index, size);
+
+    if let Some(hash) = icons.get(&key) {
+        return Ok(icon_protocol::icon_url(&hash, size));
+    }
+
+    // Render the double-density size in the same pass, so moving the window to a
+    // high-DPI display finds it cached
+    let sizes = density_sizes(size);
+    let pngs = render_icon(app.as_ref(), location.as_ref(), &icon_path, &sizes)?;
+    let mut requested = None;
+    for (&variant, png) in sizes.iter().zip(&pngs) {
+        let hash = icons.insert(&icon_cache::cache_key(&key_files, icon_index, variant), png)?;
+        if variant == size {
+            requested = Some(hash);
+        }
+    }
+
+    let hash = requested.ok_or_else(|| "Failed to get icon".to_string())?;
+    Ok(icon_protocol::icon_url(&hash, size))
+}
+
+// Decode the icon directly from its icon location when that is an .ico, executable or
+// image file, honouring the icon index; anything else is left to the shell
+fn render_icon(
+    app: Option<&AppInfo>,
+    location: Option<&(String, i32)>,
+    icon_path: &str,
+    sizes: &[u32],
+) -> Result<Vec<Vec<u8>>, String> {
+    // Icon names from desktop entries are looked up in the icon theme once per size, so
+    // each size gets the theme's closest match; image files are read as they are. Other
+    // sources' icon locations (.lnk, .url) go to the decoders below.
+    #[cfg(target_os = "linux")]
+    if let Some(icon) = app.filter(|app| app.source == "desktop_entries")
+        .and_then(|app| app.icon_path.as_deref())
+        .filter(|icon| !Path::new(icon).is_absolute() || icon_theme::can_load(Path::new(icon)))
+    {
+        return sizes.iter()
+            .map(|&size| {
+                let file = icon_theme::find_icon(icon, size, 1)
+                    .ok_or_else(|| format!("Icon {} not found in the icon theme", icon))?;
+                encode_png(&icon_theme::load_image(&file, size)?, size)
+            })
+            .collect();
+    }
+    #[cfg(not(target_os = "linux"))]
+    let _ = app;
+
+    if let Some((location, icon_index)) = location {
+        match load_images(Path::new(location), *icon_index) {
+            Ok(images) => {
+                return sizes.iter()
+                    .map(|&size| {
+                        let image = ico::best_image(&images, size)
+                            .ok_or_else(|| "Icon has no images".to_string())?;
+                        encode_png(image, size)
+                    })
+                    .collect();
+            }
+            Err(e) => log_error(&format!("Failed to decode icon {},{}: {}", location, icon_index, e)),
+        }
+    }
+
+    #[cfg(windows)]
+    return shell_icons::extract_icon_pngs(icon_path, sizes);
+    #[cfg(not(windows))]
+    Err(format!("No icon found for {}", icon_path))
+}
+
+// Where the shell would take the icon from, if it's a file we can read ourselves: an
+// internet shortcut's icon, else the shortcut's icon location, else its target, else
+// the file itself
+fn icon_location(app: Option<&AppInfo>, icon_path: &str) -> Option<(String, i32)> {
+    let (location, icon_index) = match app {
+        Some(app) if has_extension(icon_path, "url") => internet_shortcut::icon_location(app)?,
+        Some(app) if app.icon_path.is_some() => (app.icon_path.clone()?, app.icon_index),
+        Some(app) if has_extension(icon_path, "lnk") => (app.target.clone()?, 0),
+        _ => (icon_path.to_string(), 0),
+    };
+
+    let path = Path::new(&location);
+    (ico::can_decode(path) || has_extension(&location, "png")).then_some((location, icon_index))
+}
+
+// Every image of the icon at `location`: all sizes from icon files and executables, the
+// picture itself from PNGs (an IconFile or a favicon saved as .png)
+fn load_images(location: &Path, icon_index: i32) -> Result<Vec<RgbaImage>, String> {
+    if ico::can_decode(location) {
+        return ico::load_icon(location, icon_index);
+    }
+    image::open(location)
+        .map(|image| vec![image.to_rgba8()])
+        .map_err(|e| format!("Failed to read {}: {}", location.display(), e))
+}
+
+fn has_extension(path: &str, extension: &str) -> bool {
+    Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case(extension))
+}
+
+// `size` and twice `size`, within the supported range
+fn density_sizes(size: u32) -> Vec<u32> {
+    let double = (size * 2).min(MAX_ICON_SIZE);
+    if double == size { vec![size] } else { vec![size, double] }
+}
+
+// Load icons for many entries on a small pool of worker threads, handing each result
+// to `on_result` as soon as it is ready. A failure only affects its own entry.
+pub fn load_icons(
+    paths: &[String],
+    size: u32,
+    manager: &AppManager,
+    icons: &IconCache,
+    on_result: &(dyn Fn(IconResult) + Sync),
+) -> IconBatchSummary {
+    let next = AtomicUsize::new(0);
+    let summary = Mutex::new(IconBatchSummary::default());
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(2)
+        .min(MAX_ICON_WORKERS)
+        .min(paths.len());
+
+    std::thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                // Shell icon lookups need COM on the calling thread
+                #[cfg(windows)]
+                let com_initialized = unsafe { CoInitializeEx(None, COINIT_APARTMENTTHREADED).is_ok() };
+
+                while let Some(path) = paths.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let result = match cached_icon_url(path, size, manager, icons) {
+                        Ok(icon) => IconResult { path: path.clone(), icon: Some(icon), error: None },
+                        Err(e) => IconResult { path: path.clone(), icon: None, error: Some(e) },
+                    };
+
+                    {
+                        let mut summary = summary.lock().unwrap();
+                        if result.error.is_some() {
+                            summary.failed += 1;
+                        } else {
+                            summary.loaded += 1;
+                        }
+                    }
+                    on_result(result);
+                }
+
+                #[cfg(windows)]
+                if com_initialized {
+                    unsafe { CoUninitialize() };
+                }
+            });
+        }
+    });
+
+    icons.flush();
+    summary.into_inner().unwrap_or_default()
+}
+
+// Scale `image` to `size` x `size` and encode it as a PNG
+pub fn encode_png(image: &RgbaImage, size: u32) -> Result<Vec<u8>, String> {
+    let scaled;
+    let image = if image.width() == size && image.height() == size {
+        image
+    } else {
+        scaled = imageops::resize(image, size, size, FilterType::Lanczos3);
+        &scaled
+    };
+
+    let mut png_data = Vec::new();
+    let encoder = image::codecs::png::PngEncoder::new_with_quality(
+        std::io::Cursor::new(&mut png_data),
+        image::codecs::png::CompressionType::Best,
+        image::codecs::png::FilterType::Adaptive
+    );
+
+    encoder.write_image(
+        image.as_raw(),
+        size,
+        size,
+        image::ColorType::Rgba8
+    ).map_err(|e| e.to_string())?;
+
+    Ok(png_data)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use super::*;
+
+    #[test]
+    fn only_desktop_entries_use_the_icon_theme() {
+        // A shortcut's icon location is a file even when it isn't an absolute path
+        // (relative, or a Windows path read on Linux); tests run from the crate root
+        let icon = "tests/fixtures/ico/two_sizes.ico";
+        let shortcut = AppInfo {
+            path: "Tool.lnk".into(),
+            icon_path: Some(icon.into()),
+            source: "start_menu".into(),
+            ..Default::default()
+        };
+
+        let location = icon_location(Some(&shortcut), &shortcut.path);
+        assert_eq!(location, Some((icon.to_string(), 0)));
+        let pngs = render_icon(Some(&shortcut), location.as_ref(), &shortcut.path, &[16, 32]).unwrap();
+        assert_eq!(pngs.len(), 2);
+        for (png, size) in pngs.iter().zip([16, 32]) {
+            let image = image::load_from_memory(png).unwrap();
+            assert_eq!((image.width(), image.height()), (size, size));
+        }
+    }
+
+    #[test]
+    fn batches_report_every_path_once() {
+        let dir = std::env::temp_dir().join(format!("axon-icon-batch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let manager = AppManager::in_dir(dir.join("index"));
+        let icons = IconCache::in_dir(dir.join("icons"));
+        let paths: Vec<String> = [
+            "tests/fixtures/ico/two_sizes.ico",
+            "tests/fixtures/ico/masked_24bpp.ico",
+            "tests/fixtures/ico/missing.ico",
+        ].map(String::from).to_vec();
+
+        let load = || {
+            let results = Mutex::new(Vec::new());
+            let summary = load_icons(&paths, 32, &manager, &icons, &|result| results.lock().unwrap().push(result));
+            let mut results = results.into_inner().unwrap();
+            results.sort_by(|a, b| a.path.cmp(&b.path));
+            (summary, results)
+        };
+        let (summary, results) = load();
+        assert_eq!((summary.loaded, summary.failed), (2, 1));
+
+        let reported: Vec<_> = results.iter().map(|result| result.path.clone()).collect();
+        let mut expected = paths.clone();
+        expected.sort();
+        assert_eq!(reported, expected);
+
+        // The icons://loaded payloads: a URL naming a cached PNG, or the error
+        for result in &results {
+            let payload = serde_json::to_value(result).unwrap();
+            assert_eq!(payload["path"], result.path.as_str());
+            if result.path.ends_with("missing.ico") {
+                assert!(payload["icon"].is_null());
+                assert!(payload["error"].is_string());
+                continue;
+            }
+            assert!(payload["error"].is_null());
+            let url = result.icon.as_deref().unwrap();
+            assert_eq!(payload["icon"], url);
+            let hash = url.rsplit('/').next().unwrap().split('?').next().unwrap();
+            let png = icons.read(hash).unwrap();
+            let image = image::load_from_memory(&png).unwrap();
+            assert_eq!((image.width(), image.height()), (32, 32));
+        }
+
+        // A second batch is served from the cache with the same URLs
+        let (summary, again) = load();
+        let _ = fs::remove_dir_all(&dir);
+        assert_eq!((summary.loaded, summary.failed), (2, 1));
+        let icons_of = |results: &[IconResult]| results.iter().map(|result| result.icon.clone()).collect::<Vec<_>>();
+        assert_eq!(icons_of(&again), icons_of(&results));
+    }
+}
@@ -0,0 +1,416 @@
+// Internet shortcuts (.url files): an INI-style file with an [InternetShortcut]
+// section, optionally followed by [InternetShortcut.A] (ANSI) and
+// [InternetShortcut.W] (UTF-7 encoded Unicode) copies of values that don't fit the
+// file's code page. Also where their icons come from: a shortcut can name its own icon
+// file; otherwise the icon is a favicon saved for the site, or that of the program the
+// user has chosen to open the URL's scheme with.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+#[cfg(windows)]
+use winreg::enums::{HKEY_CLASSES_ROOT, HKEY_CURRENT_USER};
+#[cfg(windows)]
+use winreg::RegKey;
+use crate::discovery::AppInfo;
+#[cfg(windows)]
+use crate::lnk;
+
+// Favicons live in <app data>/favicons as <host>.ico or <host>.png
+const FAVICON_DIR: &str = "favicons";
+const FAVICON_EXTENSIONS: &[&str] = &["ico", "png"];
+
+#[cfg(windows)]
+const URL_ASSOCIATIONS: &str = "Software\\Microsoft\\Windows\\Shell\\Associations\\UrlAssociations";
+
+const SECTION: &str = "internetshortcut";
+const SECTION_ANSI: &str = "internetshortcut.a";
+const SECTION_UNICODE: &str = "internetshortcut.w";
+// Browsers write BASEURL into a [DEFAULT] section
+const SECTION_DEFAULT: &str = "default";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InternetShortcut {
+    pub url: Option<String>,
+    pub base_url: Option<String>,
+    pub working_dir: Option<String>,
+    pub icon_file: Option<String>,
+    pub icon_index: i32,
+    // Same encoding as a shell link's HotKey
+    pub hotkey: u16,
+    pub show_command: Option<u32>,
+}
+
+impl InternetShortcut {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let data = fs::read(path)
+            .map_err(|e| format!("Failed to read internet shortcut {}: {}", path.display(), e))?;
+        Self::parse(&data)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let sections = parse_sections(&decode_text(data));
+        let base_url = sections.get(SECTION_DEFAULT)
+            .and_then(|values| values.get("baseurl"))
+            .filter(|value| !value.is_empty())
+            .cloned();
+        // Browsers may save a page with nothing but its BASEURL
+        if !sections.contains_key(SECTION) && !sections.contains_key(SECTION_UNICODE) && base_url.is_none() {
+            return Err("Missing [InternetShortcut] section".into());
+        }
+
+        // Unicode copies are the most faithful, the plain section the usual source and
+        // the ANSI copy a last resort
+        let value = |key: &str| -> Option<String> {
+            let get = |section: &str| sections.get(section)
+                .and_then(|values| values.get(key))
+                .filter(|value| !value.is_empty());
+            get(SECTION_UNICODE).map(|value| decode_utf7(value))
+                .or_else(|| get(SECTION).cloned())
+                .or_else(|| get(SECTION_ANSI).cloned())
+        };
+        let number = |key: &str| value(key).and_then(|value| value.parse::<i64>().ok());
+
+        Ok(Self {
+            url: value("url"),
+            base_url,
+            working_dir: value("workingdirectory"),
+            icon_file: value("iconfile"),
+            icon_index: number("iconindex").unwrap_or(0) as i32,
+            hotkey: number("hotkey").unwrap_or(0) as u16,
+            show_command: number("showcommand").map(|command| command as u32),
+        })
+    }
+
+    // Where the shortcut leads: its URL, or the page it was saved from
+    pub fn target(&self) -> Option<&str> {
+        self.url.as_deref().or(self.base_url.as_deref())
+    }
+}
+
+// Text of the file: UTF-16 with a byte order mark, UTF-8 (with or without one), or
+// failing that the system code page, approximated as Latin-1
+fn decode_text(data: &[u8]) -> String {
+    let utf16 = |bytes: &[u8], from_bytes: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| from_bytes([pair[0], pair[1]])).collect();
+        String::from_utf16_lossy(&units)
+    };
+
+    if let Some(rest) = data.strip_prefix(&[0xFF, 0xFE]) {
+        return utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = data.strip_prefix(&[0xFE, 0xFF]) {
+        return utf16(rest, u16::from_be_bytes);
+    }
+    let data = data.strip_prefix(&[0xEF, 0xBB, 0xBF]).unwrap_or(data);
+    match std::str::from_utf8(data) {
+        Ok(text) => text.to_string(),
+        Err(_) => data.iter().map(|&byte| byte as char).collect(),
+    }
+}
+
+// Section and key names are case-insensitive and stored lowercased. As with
+// GetPrivateProfileString, the first occurrence of a key wins.
+fn parse_sections(text: &str) -> HashMap<String, HashMap<String, String>> {
+    let mut sections: HashMap<String, HashMap<String, String>> = HashMap::new();
+    let mut current: Option<String> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+            let name = name.trim().to_lowercase();
+            sections.entry(name.clone()).or_default();
+            current = Some(name);
+            continue;
+        }
+        if let (Some(section), Some((key, value))) = (&current, line.split_once('=')) {
+            sections.entry(section.clone())
+                .or_default()
+                .entry(key.trim().to_lowercase())
+                .or_insert_with(|| value.trim().to_string());
+        }
+    }
+
+    sections
+}
+
+// UTF-7 (RFC 2152) as used by [InternetShortcut.W]: "+" starts a run of modified
+// base64 UTF-16, ended by "-" (which is dropped) or any other non-base64 character;
+// "+-" is a literal "+"
+fn decode_utf7(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '+' {
+            result.push(c);
+            continue;
+        }
+        if chars.peek() == Some(&'-') {
+            chars.next();
+            result.push('+');
+            continue;
+        }
+
+        let mut units = Vec::new();
+        let (mut bits, mut bit_count) = (0u32, 0u32);
+        while let Some(&next) = chars.peek() {
+            let Some(sextet) = base64_value(next) else { break };
+            chars.next();
+            bits = (bits << 6) | sextet;
+            bit_count += 6;
+            if bit_count >= 16 {
+                bit_count -= 16;
+                units.push((bits >> bit_count) as u16);
+                bits &= (1 << bit_count) - 1;
+            }
+        }
+        if chars.peek() == Some(&'-') {
+            chars.next();
+        }
+        result.push_str(&String::from_utf16_lossy(&units));
+    }
+
+    result
+}
+
+fn base64_value(c: char) -> Option<u32> {
+    match c {
+        'A'..='Z' => Some(c as u32 - 'A' as u32),
+        'a'..='z' => Some(c as u32 - 'a' as u32 + 26),
+        '0'..='9' => Some(c as u32 - '0' as u32 + 52),
+        '+' => Some(62),
+        '/' => Some(63),
+        _ => None,
+    }
+}
+
+// Icon file and index for an internet shortcut: its IconFile if that is a local file,
+// else a cached favicon for the site, else the icon of the URL scheme's handler
+pub fn icon_location(app: &AppInfo) -> Option<(String, i32)> {
+    if let Some(file) = app.icon_path.as_deref().filter(|file| Path::new(file).is_file()) {
+        return Some((file.to_string(), app.icon_index));
+    }
+
+    let url = app.target.as_deref().and_then(|url| url::Url::parse(url).ok());
+    url.as_ref()
+        .and_then(favicon_path)
+        .map(|path| (path.to_string_lossy().into_owned(), 0))
+        .or_else(|| url.as_ref().and_then(|url| scheme_handler_icon(url.scheme())))
+        .or_else(default_browser_icon)
+}
+
+// Cached favicon for the URL's host
+pub fn favicon_path(url: &url::Url) -> Option<PathBuf> {
+    let dir = tauri::api::path::app_data_dir(&tauri::Config::default())?.join(FAVICON_DIR);
+    favicon_in(&dir, url)
+}
+
+// <host>.ico or <host>.png in `dir`, also trying the host without a leading "www."
+fn favicon_in(dir: &Path, url: &url::Url) -> Option<PathBuf> {
+    let host = url.host_str()?.to_lowercase();
+
+    for host in std::iter::once(host.as_str()).chain(host.strip_prefix("www.")) {
+        for ext in FAVICON_EXTENSIONS {
+            let path = dir.join(format!("{}.{}", host, ext));
+            if path.is_file() {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+// Icon of the program that opens http links
+pub fn default_browser_icon() -> Option<(String, i32)> {
+    scheme_handler_icon("http")
+}
+
+// Icon of the program registered for a URL scheme: the user's choice in Default Apps
+// if they made one, else the scheme's own registration
+#[cfg(windows)]
+fn scheme_handler_icon(scheme: &str) -> Option<(String, i32)> {
+    let user_choice = RegKey::predef(HKEY_CURRENT_USER)
+        .open_subkey(format!("{}\\{}\\UserChoice", URL_ASSOCIATIONS, scheme))
+        .and_then(|key| key.get_value::<String, _>("ProgId"))
+        .ok();
+
+    user_choice.iter()
+        .map(String::as_str)
+        .chain(std::iter::once(scheme))
+        .find_map(class_icon)
+}
+
+// Scheme handlers are only looked up in the Windows registry
+#[cfg(not(windows))]
+fn scheme_handler_icon(_scheme: &str) -> Option<(String, i32)> {
+    None
+}
+
+// DefaultIcon of a registered class, or the program of its open command
+#[cfg(windows)]
+fn class_icon(class: &str) -> Option<(String, i32)> {
+    let key = RegKey::predef(HKEY_CLASSES_ROOT).open_subkey(class).ok()?;
+
+    let default_icon = key.open_subkey("DefaultIcon")
+        .and_then(|icon| icon.get_value::<String, _>(""))
+        .ok()
+        .and_then(|value| parse_icon_location(&value));
+    if default_icon.is_some() {
+        return default_icon;
+    }
+
+    let command: String = key.open_subkey("shell\\open\\command")
+        .and_then(|command| command.get_value(""))
+        .ok()?;
+    command_program(&command).map(|program| (program, 0))
+}
+
+// "path,index" as stored in DefaultIcon values, with optional quotes around the path
+#[cfg(windows)]
+pub fn parse_icon_location(value: &str) -> Option<(String, i32)> {
+    let value = value.trim();
+    let (path, index) = match value.strip_prefix('"') {
+        Some(rest) => {
+            let (path, rest) = rest.split_once('"')?;
+            let index = rest.trim().strip_prefix(',').map(str::trim).unwrap_or("0");
+            (path, index)
+        }
+        None => match value.rsplit_once(',') {
+            Some((path, index)) if index.trim().parse::<i32>().is_ok() => (path, index.trim()),
+            _ => (value, "0"),
+        },
+    };
+
+    let path = lnk::expand_env_vars(path.trim());
+    (!path.is_empty()).then(|| (path, index.parse().unwrap_or(0)))
+}
+
+// Program path at the start of a shell\open\command value
+#[cfg(windows)]
+fn command_program(command: &str) -> Option<String> {
+    let command = command.trim();
+    let program = match command.strip_prefix('"') {
+        Some(rest) => rest.split('"').next()?,
+        None => {
+            // Unquoted paths may contain spaces; cut after the executable's extension
+            let lower = command.to_ascii_lowercase();
+            match lower.find(".exe") {
+                Some(end) => &command[..end + 4],
+                None => command.split_whitespace().next()?,
+            }
+        }
+    };
+
+    let program = lnk::expand_env_vars(program);
+    (!program.is_empty()).then_some(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16(text: &str, bom: [u8; 2], to_bytes: fn(u16) -> [u8; 2]) -> Vec<u8> {
+        let mut data = bom.to_vec();
+        data.extend(text.encode_utf16().flat_map(to_bytes));
+        data
+    }
+
+    #[test]
+    fn text_encodings() {
+        let text = "[InternetShortcut]\r\nURL=https://example.com/caf\u{e9}\r\n";
+        let expected = Some("https://example.com/caf\u{e9}".to_string());
+
+        let utf16_le = utf16(text, [0xFF, 0xFE], u16::to_le_bytes);
+        let utf16_be = utf16(text, [0xFE, 0xFF], u16::to_be_bytes);
+        let utf8_bom = [&[0xEF, 0xBB, 0xBF][..], text.as_bytes()].concat();
+        // Not valid UTF-8, so read as the code page: 0xE9 is "é" in Latin-1
+        let latin1: Vec<u8> = text.chars().map(|c| c as u8).collect();
+
+        for data in [utf16_le, utf16_be, utf8_bom, latin1] {
+            assert_eq!(InternetShortcut::parse(&data).unwrap().url, expected);
+        }
+    }
+
+    #[test]
+    fn unicode_section_is_utf7() {
+        let data = b"[InternetShortcut]\r\n\
+            URL=https://example.com/?\r\n\
+            IconFile=C:\\Icons\\plain.ico\r\n\
+            [InternetShortcut.W]\r\n\
+            URL=https://example.com/+AOk-t+AOk-?a+-b\r\n\
+            WorkingDirectory=C:\\+ZeVnLA\r\n";
+        let shortcut = InternetShortcut::parse(data).unwrap();
+
+        assert_eq!(shortcut.url.as_deref(), Some("https://example.com/\u{e9}t\u{e9}?a+b"));
+        // A base64 run may end at the end of the value without a "-"
+        assert_eq!(shortcut.working_dir.as_deref(), Some("C:\\\u{65e5}\u{672c}"));
+        // Keys missing from the Unicode copy come from the plain section
+        assert_eq!(shortcut.icon_file.as_deref(), Some("C:\\Icons\\plain.ico"));
+    }
+
+    #[test]
+    fn first_occurrence_of_a_key_wins() {
+        let data = b"[InternetShortcut]\nurl=https://first.example\nIconIndex=2\n\
+            [InternetShortcut]\nURL=https://second.example\nIconIndex=5\nHotKey=1604\n";
+        let shortcut = InternetShortcut::parse(data).unwrap();
+
+        assert_eq!(shortcut.url.as_deref(), Some("https://first.example"));
+        assert_eq!(shortcut.icon_index, 2);
+        assert_eq!(shortcut.hotkey, 1604);
+    }
+
+    #[test]
+    fn base_url_only() {
+        let shortcut = InternetShortcut::parse(b"[DEFAULT]\r\nBASEURL=https://example.com/page\r\n").unwrap();
+
+        assert_eq!(shortcut.url, None);
+        assert_eq!(shortcut.target(), Some("https://example.com/page"));
+    }
+
+    #[test]
+    fn cached_favicons_by_host() {
+        let dir = std::env::temp_dir().join(format!("axon-favicons-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("example.com.png"), b"png").unwrap();
+        fs::write(dir.join("docs.rs.ico"), b"ico").unwrap();
+        fs::write(dir.join("docs.rs.png"), b"png").unwrap();
+        let url = |url: &str| url::Url::parse(url).unwrap();
+
+        // A leading "www." falls back to the bare host, and .ico is preferred
+        assert_eq!(favicon_in(&dir, &url("https://WWW.Example.com/page")), Some(dir.join("example.com.png")));
+        assert_eq!(favicon_in(&dir, &url("https://docs.rs/")), Some(dir.join("docs.rs.ico")));
+        assert_eq!(favicon_in(&dir, &url("https://other.example/")), None);
+        assert_eq!(favicon_in(&dir, &url("file:///C:/page.html")), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn icon_file_comes_before_the_favicon() {
+        let dir = std::env::temp_dir().join(format!("axon-icon-file-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let icon = dir.join("site.ico");
+        fs::write(&icon, b"ico").unwrap();
+        let app = AppInfo {
+            target: Some("https://example.com/".into()),
+            icon_path: Some(icon.to_string_lossy().into_owned()),
+            icon_index: 2,
+            ..Default::default()
+        };
+
+        assert_eq!(icon_location(&app), Some((icon.to_string_lossy().into_owned(), 2)));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn missing_section_is_an_error() {
+        assert!(InternetShortcut::parse(b"URL=https://example.com\r\n").is_err());
+        assert!(InternetShortcut::parse(b"[Other]\r\nURL=https://example.com\r\n").is_err());
+        assert!(InternetShortcut::parse(b"").is_err());
+    }
+}
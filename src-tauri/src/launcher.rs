@@ -0,0 +1,546 @@
+// Starting entries. The Launcher trait hides how the platform opens files, programs
+// and URLs: ShellExecuteEx on Windows, direct spawning or xdg-open on Linux. Sources
+// build a LaunchRequest and hand it to the Launcher they are given, which the app
+// manages as the platform's SystemLauncher.
+
+use std::fmt;
+use std::path::Path;
+use serde::Serialize;
+#[cfg(windows)]
+use windows::core::{HSTRING, PCWSTR, PWSTR};
+#[cfg(windows)]
+use windows::Win32::Foundation::{
+    CloseHandle, ERROR_ACCESS_DENIED, ERROR_CANCELLED, ERROR_FILE_NOT_FOUND, ERROR_NOT_ENOUGH_MEMORY,
+    ERROR_NO_ASSOCIATION, ERROR_OUTOFMEMORY, ERROR_PATH_NOT_FOUND, WIN32_ERROR,
+};
+#[cfg(windows)]
+use windows::Win32::System::Threading::{
+    CreateProcessW, CREATE_UNICODE_ENVIRONMENT, PROCESS_INFORMATION, STARTF_USESHOWWINDOW, STARTUPINFOW,
+};
+#[cfg(windows)]
+use windows::Win32::UI::Shell::{ShellExecuteExW, SEE_MASK_FLAG_NO_UI, SEE_MASK_NOASYNC, SHELLEXECUTEINFOW};
+use crate::discovery::AppInfo;
+use crate::lnk;
+#[cfg(windows)]
+use crate::log_error;
+use crate::settings::LaunchProfile;
+
+pub trait Launcher: Send + Sync {
+    // Start the request's file, program or URL; returns once it has been started
+    fn open(&self, request: &LaunchRequest) -> Result<(), LaunchError>;
+    // Show a folder in the file manager
+    fn open_folder(&self, path: &str) -> Result<(), LaunchError>;
+}
+
+#[cfg(windows)]
+pub use self::ShellLauncher as SystemLauncher;
+#[cfg(target_os = "linux")]
+pub use self::XdgLauncher as SystemLauncher;
+
+// Why a launch failed, serialized for the frontend as e.g.
+// { "kind": "file_not_found", "path": "C:\\..." } so it can offer a fix
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LaunchError {
+    // The file to open, or the program a shortcut points to, is gone
+    FileNotFound { path: String },
+    // A directory on the way there is gone, e.g. an unmapped drive
+    PathNotFound { path: String },
+    AccessDenied { path: String },
+    // Nothing is registered to open this kind of file
+    NoAssociation { path: String },
+    OutOfMemory,
+    // The user declined the elevation prompt
+    ElevationCancelled,
+    Other { message: String },
+}
+
+impl LaunchError {
+    #[cfg(windows)]
+    fn from_windows(error: &windows::core::Error, path: &str) -> Self {
+        let path = path.to_string();
+        match WIN32_ERROR::from_error(error) {
+            Some(ERROR_FILE_NOT_FOUND) => LaunchError::FileNotFound { path },
+            Some(ERROR_PATH_NOT_FOUND) => LaunchError::PathNotFound { path },
+            Some(ERROR_ACCESS_DENIED) => LaunchError::AccessDenied { path },
+            Some(ERROR_NO_ASSOCIATION) => LaunchError::NoAssociation { path },
+            Some(ERROR_NOT_ENOUGH_MEMORY) | Some(ERROR_OUTOFMEMORY) => LaunchError::OutOfMemory,
+            Some(ERROR_CANCELLED) => LaunchError::ElevationCancelled,
+            _ => LaunchError::Other { message: format!("Failed to open {}: {}", path, error.message()) },
+        }
+    }
+
+    pub fn from_io(error: &std::io::Error, path: &str) -> Self {
+        let path = path.to_string();
+        match error.kind() {
+            std::io::ErrorKind::NotFound => LaunchError::FileNotFound { path },
+            std::io::ErrorKind::PermissionDenied => LaunchError::AccessDenied { path },
+            std::io::ErrorKind::OutOfMemory => LaunchError::OutOfMemory,
+            _ => LaunchError::Other { message: format!("Failed to open {}: {}", path, error) },
+        }
+    }
+}
+
+impl fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaunchError::FileNotFound { path } => write!(f, "File not found: {}", path),
+            LaunchError::PathNotFound { path } => write!(f, "Path not found: {}", path),
+            LaunchError::AccessDenied { path } => write!(f, "Access denied: {}", path),
+            LaunchError::NoAssociation { path } => write!(f, "No application is associated with {}", path),
+            LaunchError::OutOfMemory => write!(f, "Not enough memory to launch the application"),
+            LaunchError::ElevationCancelled => write!(f, "Elevation was cancelled"),
+            LaunchError::Other { message } => write!(f, "{}", message),
+        }
+    }
+}
+
+// Errors from settings and lookups that happen before anything is launched
+impl From<String> for LaunchError {
+    fn from(message: String) -> Self {
+        LaunchError::Other { message }
+    }
+}
+
+// What to start and how
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchRequest {
+    // File, program or URL to open
+    pub file: String,
+    // Command line appended after the file, as a single string
+    pub arguments: Option<String>,
+    pub working_dir: Option<String>,
+    // Shell link ShowCommand; only ShellExecute can apply it
+    #[cfg_attr(not(windows), allow(dead_code))]
+    pub show_command: Option<u32>,
+    pub run_as_admin: bool,
+    pub env: Vec<(String, String)>,
+}
+
+impl LaunchRequest {
+    pub fn new(file: &str, arguments: Option<&str>, working_dir: Option<&str>, show_command: Option<u32>) -> Self {
+        Self {
+            file: file.to_string(),
+            arguments: arguments.map(String::from),
+            working_dir: working_dir.map(String::from),
+            show_command,
+            ..Default::default()
+        }
+    }
+
+    // An entry with a profile's overrides. Shortcuts are started through their target
+    // so the profile's arguments can follow the ones stored in the shortcut; internet
+    // shortcuts take no arguments.
+    pub fn with_profile(app: &AppInfo, profile: &LaunchProfile) -> Self {
+        let lower = app.path.to_lowercase();
+        let is_url = lower.ends_with(".url");
+        let file = match app.target.as_deref() {
+            Some(target) if lower.ends_with(".lnk") && Path::new(target).exists() => target.to_string(),
+            _ => app.path.clone(),
+        };
+        let arguments = if is_url {
+            None
+        } else {
+            let joined = [app.arguments.as_deref(), profile.arguments.as_deref()]
+                .into_iter()
+                .flatten()
+                .filter(|args| !args.trim().is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            (!joined.is_empty()).then_some(joined)
+        };
+
+        Self {
+            file,
+            arguments,
+            working_dir: profile.working_dir.clone().or_else(|| app.working_dir.clone()),
+            show_command: profile.window_state.map(|state| state.show_command()).or(app.show_command),
+            run_as_admin: profile.run_as_admin,
+            env: profile.env.iter()
+                .map(|(name, value)| (name.clone(), lnk::expand_env_vars(value)))
+                .collect(),
+        }
+    }
+}
+
+// Records requests instead of starting anything, for testing what sources launch
+#[cfg(test)]
+#[derive(Default)]
+pub struct RecordingLauncher {
+    pub opened: std::sync::Mutex<Vec<LaunchRequest>>,
+    pub folders: std::sync::Mutex<Vec<String>>,
+}
+
+#[cfg(test)]
+impl Launcher for RecordingLauncher {
+    fn open(&self, request: &LaunchRequest) -> Result<(), LaunchError> {
+        self.opened.lock().unwrap().push(request.clone());
+        Ok(())
+    }
+
+    fn open_folder(&self, path: &str) -> Result<(), LaunchError> {
+        self.folders.lock().unwrap().push(path.to_string());
+        Ok(())
+    }
+}
+
+// ShellExecuteEx, which applies file associations, shortcuts and elevation
+#[cfg(windows)]
+pub struct ShellLauncher;
+
+#[cfg(windows)]
+impl Launcher for ShellLauncher {
+    fn open(&self, request: &LaunchRequest) -> Result<(), LaunchError> {
+        if !request.env.is_empty() {
+            return create_process(request);
+        }
+        shell_execute(if request.run_as_admin { "runas" } else { "open" }, request)
+    }
+
+    fn open_folder(&self, path: &str) -> Result<(), LaunchError> {
+        shell_execute("explore", &LaunchRequest::new(path, None, None, None))
+    }
+}
+
+// Map a shortcut's ShowCommand onto the values ShellExecute accepts
+#[cfg(windows)]
+fn show_window_cmd(show_command: Option<u32>) -> windows::Win32::UI::WindowsAndMessaging::SHOW_WINDOW_CMD {
+    use windows::Win32::UI::WindowsAndMessaging::{SW_SHOWMAXIMIZED, SW_SHOWMINNOACTIVE, SW_SHOWNORMAL};
+
+    match show_command {
+        Some(3) => SW_SHOWMAXIMIZED,
+        Some(7) => SW_SHOWMINNOACTIVE,
+        _ => SW_SHOWNORMAL,
+    }
+}
+
+#[cfg(windows)]
+fn shell_execute(verb: &str, launch: &LaunchRequest) -> Result<(), LaunchError> {
+    let file = HSTRING::from(launch.file.as_str());
+    let verb = HSTRING::from(verb);
+    let arguments = launch.arguments.as_deref().filter(|args| !args.is_empty()).map(HSTRING::from);
+    let working_dir = launch.working_dir.as_deref().filter(|dir| !dir.is_empty()).map(HSTRING::from);
+
+    let mut info = SHELLEXECUTEINFOW {
+        cbSize: std::mem::size_of::<SHELLEXECUTEINFOW>() as u32,
+        // NOASYNC makes the call return only once the process has been created, so
+        // failures are reported here
+        fMask: SEE_MASK_NOASYNC | SEE_MASK_FLAG_NO_UI,
+        lpVerb: PCWSTR(verb.as_ptr()),
+        lpFile: PCWSTR(file.as_ptr()),
+        lpParameters: arguments.as_ref().map(|a| PCWSTR(a.as_ptr())).unwrap_or(PCWSTR::null()),
+        lpDirectory: working_dir.as_ref().map(|d| PCWSTR(d.as_ptr())).unwrap_or(PCWSTR::null()),
+        nShow: show_window_cmd(launch.show_command).0,
+        ..Default::default()
+    };
+
+    unsafe { ShellExecuteExW(&mut info) }.map_err(|e| {
+        log_error(&format!("ShellExecuteEx failed for {}: {}", launch.file, e));
+        LaunchError::from_windows(&e, &launch.file)
+    })
+}
+
+// Start a program with environment overrides. ShellExecute has no environment
+// parameter, so this goes through CreateProcess with an explicit environment block
+// instead of changing our own environment, which other threads read concurrently.
+#[cfg(windows)]
+fn create_process(launch: &LaunchRequest) -> Result<(), LaunchError> {
+    const PROGRAMS: &[&str] = &["exe", "com", "bat", "cmd"];
+    let is_program = Path::new(&launch.file).extension()
+        .is_some_and(|ext| PROGRAMS.iter().any(|program| ext.eq_ignore_ascii_case(program)));
+    if !is_program {
+        return Err(LaunchError::Other {
+            message: format!("Environment variables can only be set for programs, not {}", launch.file),
+        });
+    }
+    if launch.run_as_admin {
+        return Err(LaunchError::Other {
+            message: "Environment variables can't be set for elevated launches".to_string(),
+        });
+    }
+
+    let mut command_line = windows_command_line(&launch.file, launch.arguments.as_deref());
+    let environment = environment_block(std::env::vars_os(), &launch.env);
+    let working_dir = launch.working_dir.as_deref().filter(|dir| !dir.is_empty()).map(HSTRING::from);
+    let startup = STARTUPINFOW {
+        cb: std::mem::size_of::<STARTUPINFOW>() as u32,
+        dwFlags: STARTF_USESHOWWINDOW,
+        wShowWindow: show_window_cmd(launch.show_command).0 as u16,
+        ..Default::default()
+    };
+    let mut process = PROCESS_INFORMATION::default();
+
+    let result = unsafe {
+        CreateProcessW(
+            PCWSTR::null(),
+            PWSTR(command_line.as_mut_ptr()),
+            None,
+            None,
+            false,
+            CREATE_UNICODE_ENVIRONMENT,
+            Some(environment.as_ptr() as *const std::ffi::c_void),
+            working_dir.as_ref().map(|dir| PCWSTR(dir.as_ptr())).unwrap_or(PCWSTR::null()),
+            &startup,
+            &mut process,
+        )
+    };
+
+    match result {
+        Ok(()) => {
+            unsafe {
+                let _ = CloseHandle(process.hThread);
+                let _ = CloseHandle(process.hProcess);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            log_error(&format!("CreateProcess failed for {}: {}", launch.file, e));
+            Err(LaunchError::from_windows(&e, &launch.file))
+        }
+    }
+}
+
+// NUL-terminated UTF-16 command line: the program, quoted, then the arguments as given
+#[cfg(any(windows, test))]
+fn windows_command_line(file: &str, arguments: Option<&str>) -> Vec<u16> {
+    let mut line = format!("\"{}\"", file);
+    if let Some(arguments) = arguments.filter(|args| !args.trim().is_empty()) {
+        line.push(' ');
+        line.push_str(arguments);
+    }
+    line.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+// The inherited environment with `overrides` applied, as a CreateProcess environment
+// block: NAME=VALUE strings sorted by name, each NUL-terminated, then a final NUL.
+// Names compare case-insensitively, as Windows does.
+#[cfg(any(windows, test))]
+fn environment_block(
+    inherited: impl Iterator<Item = (std::ffi::OsString, std::ffi::OsString)>,
+    overrides: &[(String, String)],
+) -> Vec<u16> {
+    let mut variables: Vec<(String, String)> = inherited
+        .map(|(name, value)| (name.to_string_lossy().into_owned(), value.to_string_lossy().into_owned()))
+        .filter(|(name, _)| !overrides.iter().any(|(over, _)| over.eq_ignore_ascii_case(name)))
+        .chain(overrides.iter().cloned())
+        .collect();
+    variables.sort_by_key(|(name, _)| name.to_uppercase());
+
+    let mut block = Vec::new();
+    for (name, value) in &variables {
+        block.extend(format!("{}={}", name, value).encode_utf16());
+        block.push(0);
+    }
+    if block.is_empty() {
+        block.push(0);
+    }
+    block.push(0);
+    block
+}
+
+// Programs are spawned directly, everything else goes to xdg-open (or gio open). The
+// child gets its own process group and no inherited stdio, and is reaped in the
+// background, so it outlives the launcher and never becomes a zombie. Openers are
+// waited on briefly, so a file without a handler is reported rather than dropped.
+#[cfg(target_os = "linux")]
+pub struct XdgLauncher;
+
+#[cfg(target_os = "linux")]
+const OPENERS: [&[&str]; 2] = [&["xdg-open"], &["gio", "open"]];
+// Openers normally exit at once; one still running after this has started the
+// handler in the foreground (xdg-open's generic fallback) and is left to it
+#[cfg(target_os = "linux")]
+const OPENER_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+#[cfg(target_os = "linux")]
+impl Launcher for XdgLauncher {
+    fn open(&self, request: &LaunchRequest) -> Result<(), LaunchError> {
+        match crate::desktop_entry::find_executable(&request.file) {
+            Some(program) => spawn_detached(&program_argv(&program, request), request),
+            None => open_with_handler(&request.file, request),
+        }
+    }
+
+    fn open_folder(&self, path: &str) -> Result<(), LaunchError> {
+        if !Path::new(path).is_dir() {
+            return Err(LaunchError::PathNotFound { path: path.to_string() });
+        }
+        open_with_handler(path, &LaunchRequest::default())
+    }
+}
+
+// The program followed by the request's arguments, through pkexec when elevated
+#[cfg(target_os = "linux")]
+fn program_argv(program: &Path, request: &LaunchRequest) -> Vec<String> {
+    let mut argv = Vec::new();
+    if request.run_as_admin {
+        argv.push("pkexec".to_string());
+    }
+    argv.push(program.to_string_lossy().into_owned());
+    if let Some(arguments) = &request.arguments {
+        argv.extend(crate::desktop_entry::split_exec(arguments));
+    }
+    argv
+}
+
+// Hand a document, folder or URL to the desktop's default handler
+#[cfg(target_os = "linux")]
+fn open_with_handler(target: &str, request: &LaunchRequest) -> Result<(), LaunchError> {
+    let is_url = url::Url::parse(target).is_ok_and(|url| url.scheme().len() > 1);
+    if !is_url && !Path::new(target).exists() {
+        return Err(LaunchError::FileNotFound { path: target.to_string() });
+    }
+
+    for opener in OPENERS {
+        let argv: Vec<String> = opener.iter().map(|arg| arg.to_string())
+            .chain(std::iter::once(target.to_string()))
+            .collect();
+        let child = match command(&argv, request)?.spawn() {
+            Ok(child) => child,
+            // Try the next opener only if this one isn't installed
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(LaunchError::from_io(&e, &argv[0])),
+        };
+        return wait_for_opener(child, opener[0], target);
+    }
+    Err(LaunchError::NoAssociation { path: target.to_string() })
+}
+
+#[cfg(target_os = "linux")]
+fn wait_for_opener(mut child: std::process::Child, opener: &str, target: &str) -> Result<(), LaunchError> {
+    let deadline = std::time::Instant::now() + OPENER_TIMEOUT;
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return opener_result(opener, status.code(), target),
+            Ok(None) if std::time::Instant::now() < deadline => {
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            Ok(None) => {
+                std::thread::spawn(move || {
+                    let _ = child.wait();
+                });
+                return Ok(());
+            }
+            Err(e) => return Err(LaunchError::from_io(&e, opener)),
+        }
+    }
+}
+
+// An opener's exit status as a launch result. xdg-open exits with 2 for a missing
+// file, 3 when no tool to open it is installed and 4 when opening failed, which is
+// what a file type without a handler gives; gio open only reports failure.
+#[cfg(target_os = "linux")]
+fn opener_result(opener: &str, code: Option<i32>, target: &str) -> Result<(), LaunchError> {
+    let path = target.to_string();
+    match (opener, code) {
+        (_, Some(0)) => Ok(()),
+        ("xdg-open", Some(2)) => Err(LaunchError::FileNotFound { path }),
+        ("xdg-open", Some(3 | 4)) | ("gio", Some(_)) => Err(LaunchError::NoAssociation { path }),
+        (_, Some(code)) => Err(LaunchError::Other {
+            message: format!("{} failed to open {} (exit status {})", opener, target, code),
+        }),
+        (_, None) => Err(LaunchError::Other {
+            message: format!("{} was killed while opening {}", opener, target),
+        }),
+    }
+}
+
+// The command for `argv` with the request's environment and working directory, in
+// its own process group and without our stdio
+#[cfg(target_os = "linux")]
+fn command(argv: &[String], request: &LaunchRequest) -> Result<std::process::Command, LaunchError> {
+    use std::os::unix::process::CommandExt;
+    use std::process::{Command, Stdio};
+
+    let (program, args) = argv.split_first()
+        .ok_or_else(|| LaunchError::Other { message: "Nothing to launch".to_string() })?;
+
+    let mut command = Command::new(program);
+    command.args(args)
+        .envs(request.env.iter().map(|(name, value)| (name, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0);
+    if let Some(dir) = request.working_dir.as_deref().filter(|dir| !dir.is_empty()) {
+        if !Path::new(dir).is_dir() {
+            return Err(LaunchError::PathNotFound { path: dir.to_string() });
+        }
+        command.current_dir(dir);
+    }
+    Ok(command)
+}
+
+#[cfg(target_os = "linux")]
+fn spawn_detached(argv: &[String], request: &LaunchRequest) -> Result<(), LaunchError> {
+    let mut child = command(argv, request)?
+        .spawn()
+        .map_err(|e| LaunchError::from_io(&e, &argv[0]))?;
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn program_argv_splits_arguments_and_elevates() {
+        let request = LaunchRequest::new("/usr/bin/editor", Some(r#"--title "My Notes" -n"#), None, None);
+        let program = Path::new("/usr/bin/editor");
+
+        assert_eq!(program_argv(program, &request), ["/usr/bin/editor", "--title", "My Notes", "-n"]);
+        let elevated = LaunchRequest { run_as_admin: true, arguments: None, ..request };
+        assert_eq!(program_argv(program, &elevated), ["pkexec", "/usr/bin/editor"]);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn opener_exit_status() {
+        let target = "/home/user/file.xyz";
+        assert_eq!(opener_result("xdg-open", Some(0), target), Ok(()));
+        assert_eq!(opener_result("xdg-open", Some(2), target), Err(LaunchError::FileNotFound { path: target.into() }));
+        for code in [3, 4] {
+            assert_eq!(opener_result("xdg-open", Some(code), target), Err(LaunchError::NoAssociation { path: target.into() }));
+        }
+        assert_eq!(opener_result("gio", Some(2), target), Err(LaunchError::NoAssociation { path: target.into() }));
+        assert!(matches!(opener_result("xdg-open", Some(1), target), Err(LaunchError::Other { .. })));
+        assert!(matches!(opener_result("xdg-open", None, target), Err(LaunchError::Other { .. })));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn missing_files_and_directories_fail_before_spawning() {
+        let missing = "/nonexistent/axon/file.txt";
+        assert_eq!(XdgLauncher.open(&LaunchRequest::new(missing, None, None, None)),
+            Err(LaunchError::FileNotFound { path: missing.into() }));
+        assert_eq!(XdgLauncher.open_folder("/nonexistent/axon"),
+            Err(LaunchError::PathNotFound { path: "/nonexistent/axon".into() }));
+
+        let bad_dir = LaunchRequest::new("/bin/sh", None, Some("/nonexistent/axon"), None);
+        assert_eq!(XdgLauncher.open(&bad_dir), Err(LaunchError::PathNotFound { path: "/nonexistent/axon".into() }));
+    }
+
+    #[test]
+    fn environment_block_overrides_case_insensitively() {
+        let inherited = [("Path", "C:\\Windows"), ("TEMP", "C:\\Temp"), ("appdata", "C:\\Roaming")]
+            .map(|(name, value)| (name.into(), value.into()));
+        let overrides = [("PATH".to_string(), "C:\\Tools".to_string()), ("Zeta".to_string(), String::new())];
+        let block = environment_block(inherited.into_iter(), &overrides);
+
+        let text = String::from_utf16(&block).unwrap();
+        assert_eq!(text, "appdata=C:\\Roaming\0PATH=C:\\Tools\0TEMP=C:\\Temp\0Zeta=\0\0");
+        assert_eq!(environment_block(std::iter::empty(), &[]), [0, 0]);
+    }
+
+    #[test]
+    fn command_line_quotes_the_program() {
+        let line = windows_command_line("C:\\Program Files\\Tool\\tool.exe", Some("--open \"a b\""));
+        assert_eq!(String::from_utf16(&line).unwrap(), "\"C:\\Program Files\\Tool\\tool.exe\" --open \"a b\"\0");
+        let line = windows_command_line("C:\\tool.exe", Some("  "));
+        assert_eq!(String::from_utf16(&line).unwrap(), "\"C:\\tool.exe\"\0");
+    }
+}
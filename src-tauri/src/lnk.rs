@@ -0,0 +1,634 @@
+// Pure-Rust reader for Shell Link (.lnk) files as described in [MS-SHLLINK].
+// Replaces the IShellLinkW/IPersistFile round-trip so shortcuts can be resolved
+// without COM, without the MAX_PATH target buffer and on any OS.
+
+use std::fs;
+use std::path::Path;
+
+const HEADER_SIZE: u32 = 0x4C;
+const LINK_CLSID: [u8; 16] = [
+    0x01, 0x14, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0xC0, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x46,
+];
+
+// LinkFlags
+const HAS_LINK_TARGET_ID_LIST: u32 = 1 << 0;
+const HAS_LINK_INFO: u32 = 1 << 1;
+const HAS_NAME: u32 = 1 << 2;
+const HAS_RELATIVE_PATH: u32 = 1 << 3;
+const HAS_WORKING_DIR: u32 = 1 << 4;
+const HAS_ARGUMENTS: u32 = 1 << 5;
+const HAS_ICON_LOCATION: u32 = 1 << 6;
+const IS_UNICODE: u32 = 1 << 7;
+const FORCE_NO_LINK_INFO: u32 = 1 << 8;
+
+// LinkInfoFlags
+const VOLUME_ID_AND_LOCAL_BASE_PATH: u32 = 1 << 0;
+const COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX: u32 = 1 << 1;
+
+// ExtraData block signatures
+const ENVIRONMENT_VARIABLE_DATA_BLOCK: u32 = 0xA000_0001;
+const ICON_ENVIRONMENT_DATA_BLOCK: u32 = 0xA000_0007;
+const KNOWN_FOLDER_DATA_BLOCK: u32 = 0xA000_000B;
+
+// Shell item extension block carrying the long (Unicode) file name
+const FILE_ENTRY_EXTENSION_SIGNATURE: u32 = 0xBEEF_0004;
+
+// {20D04FE0-3AEA-1069-A2D8-08002B30309D}
+const CLSID_MY_COMPUTER: &str = "20D04FE0-3AEA-1069-A2D8-08002B30309D";
+
+// Known folders we can map back onto environment variables when a shortcut
+// only records its target relative to a KNOWNFOLDERID.
+const KNOWN_FOLDERS: &[(&str, &str)] = &[
+    ("905E63B6-C1BF-494E-B29C-65B732D3D21A", "%ProgramFiles%"),
+    ("7C5A40EF-A0FB-4BFC-874A-C0F2E0B9FA8E", "%ProgramFiles(x86)%"),
+    ("6D809377-6AF0-444B-8957-A3773F02200E", "%ProgramW6432%"),
+    ("F7F1ED05-9F6D-47A2-AAAE-29D317C6F066", "%CommonProgramFiles%"),
+    ("F38BF404-1D43-42F2-9305-67DE0B28FC23", "%windir%"),
+    ("1AC14E77-02E7-4E5D-B744-2EB1AE5198B7", "%windir%\\System32"),
+    ("D65231B0-B2F1-4857-A4CE-A8E7C6EA7D27", "%windir%\\SysWOW64"),
+    ("F1B32785-6FBA-4FCF-9D55-7B8E7F157091", "%LOCALAPPDATA%"),
+    ("3EB685DB-65F9-4CF6-A03A-E3EF65729F3D", "%APPDATA%"),
+    ("62AB5D82-FDC1-4DC3-A9DD-070D1D495D97", "%ProgramData%"),
+    ("5E6C858F-0E22-4760-9AFE-EA3317B67173", "%USERPROFILE%"),
+    ("5CD7AEE2-2219-4A67-B85D-6C9CE15660CB", "%LOCALAPPDATA%\\Programs"),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShellLink {
+    pub link_flags: u32,
+    pub file_attributes: u32,
+    pub file_size: u32,
+    pub icon_index: i32,
+    pub show_command: u32,
+    pub hotkey: u16,
+    // Path assembled from the LinkTargetIDList shell items, if they describe a file system path
+    pub id_list_path: Option<String>,
+    pub local_base_path: Option<String>,
+    pub network_path: Option<String>,
+    pub name: Option<String>,
+    pub relative_path: Option<String>,
+    pub working_dir: Option<String>,
+    pub arguments: Option<String>,
+    pub icon_location: Option<String>,
+    // Unexpanded targets from the environment-variable ExtraData blocks
+    pub environment_target: Option<String>,
+    pub icon_environment_target: Option<String>,
+    // Known folder the target lives under, plus the path below it taken from the IDList
+    pub known_folder_id: Option<String>,
+    pub known_folder_path: Option<String>,
+}
+
+impl ShellLink {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let data = fs::read(path)
+            .map_err(|e| format!("Failed to read shortcut {}: {}", path.display(), e))?;
+        Self::parse(&data)
+    }
+
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader::new(data);
+
+        if reader.u32()? != HEADER_SIZE {
+            return Err("Invalid shell link header size".into());
+        }
+        if reader.bytes(16)? != LINK_CLSID {
+            return Err("Invalid shell link CLSID".into());
+        }
+
+        let link_flags = reader.u32()?;
+        let file_attributes = reader.u32()?;
+        reader.skip(24)?; // creation, access and write FILETIMEs
+        let file_size = reader.u32()?;
+        let icon_index = reader.u32()? as i32;
+        let show_command = reader.u32()?;
+        let hotkey = reader.u16()?;
+        reader.skip(10)?; // reserved
+
+        let mut link = ShellLink {
+            link_flags,
+            file_attributes,
+            file_size,
+            icon_index,
+            show_command,
+            hotkey,
+            ..Default::default()
+        };
+
+        let mut id_list = Vec::new();
+        if link.has(HAS_LINK_TARGET_ID_LIST) {
+            let size = reader.u16()? as usize;
+            id_list = parse_id_list(reader.bytes(size)?);
+            link.id_list_path = id_list_path(&id_list);
+        }
+
+        if link.has(HAS_LINK_INFO) {
+            let start = reader.pos;
+            let size = reader.u32()? as usize;
+            reader.skip(size.saturating_sub(4))?;
+            let info = &data[start..reader.pos];
+            if !link.has(FORCE_NO_LINK_INFO) {
+                link.parse_link_info(info)?;
+            }
+        }
+
+        let unicode = link.has(IS_UNICODE);
+        if link.has(HAS_NAME) {
+            link.name = Some(reader.string_data(unicode)?);
+        }
+        if link.has(HAS_RELATIVE_PATH) {
+            link.relative_path = Some(reader.string_data(unicode)?);
+        }
+        if link.has(HAS_WORKING_DIR) {
+            link.working_dir = Some(reader.string_data(unicode)?);
+        }
+        if link.has(HAS_ARGUMENTS) {
+            link.arguments = Some(reader.string_data(unicode)?);
+        }
+        if link.has(HAS_ICON_LOCATION) {
+            link.icon_location = Some(reader.string_data(unicode)?);
+        }
+
+        // ExtraData is optional and frequently truncated by third-party tools, so a
+        // malformed block ends parsing instead of failing the whole shortcut.
+        while let Ok(size) = reader.u32() {
+            if size < 8 {
+                break;
+            }
+            let Ok(block) = reader.bytes(size as usize - 4) else { break };
+            let signature = u32::from_le_bytes([block[0], block[1], block[2], block[3]]);
+            let body = &block[4..];
+
+            match signature {
+                ENVIRONMENT_VARIABLE_DATA_BLOCK => {
+                    link.environment_target = environment_block_target(body);
+                }
+                ICON_ENVIRONMENT_DATA_BLOCK => {
+                    link.icon_environment_target = environment_block_target(body);
+                }
+                KNOWN_FOLDER_DATA_BLOCK if body.len() >= 20 => {
+                    let folder_id = format_guid(&body[..16]);
+                    let offset = u32::from_le_bytes([body[16], body[17], body[18], body[19]]);
+                    link.known_folder_path = known_folder_suffix(&id_list, offset as usize);
+                    link.known_folder_id = Some(folder_id);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(link)
+    }
+
+    fn has(&self, flag: u32) -> bool {
+        self.link_flags & flag != 0
+    }
+
+    fn parse_link_info(&mut self, info: &[u8]) -> Result<(), String> {
+        let mut reader = Reader::new(info);
+        let _size = reader.u32()?;
+        let header_size = reader.u32()?;
+        let flags = reader.u32()?;
+        let _volume_id_offset = reader.u32()?;
+        let local_base_path_offset = reader.u32()? as usize;
+        let network_link_offset = reader.u32()? as usize;
+        let common_path_suffix_offset = reader.u32()? as usize;
+
+        let (local_base_path_unicode, common_path_suffix_unicode) = if header_size >= 0x24 {
+            (reader.u32()? as usize, reader.u32()? as usize)
+        } else {
+            (0, 0)
+        };
+
+        let suffix = if common_path_suffix_unicode != 0 {
+            utf16_cstr_at(info, common_path_suffix_unicode)
+        } else {
+            ansi_cstr_at(info, common_path_suffix_offset)
+        }.unwrap_or_default();
+
+        if flags & VOLUME_ID_AND_LOCAL_BASE_PATH != 0 {
+            let base = if local_base_path_unicode != 0 {
+                utf16_cstr_at(info, local_base_path_unicode)
+            } else {
+                ansi_cstr_at(info, local_base_path_offset)
+            };
+            if let Some(base) = base.filter(|b| !b.is_empty()) {
+                self.local_base_path = Some(base + &suffix);
+            }
+        }
+
+        if flags & COMMON_NETWORK_RELATIVE_LINK_AND_PATH_SUFFIX != 0 {
+            let link = info.get(network_link_offset..)
+                .ok_or_else(|| "Invalid network link offset".to_string())?;
+            let mut reader = Reader::new(link);
+            let _size = reader.u32()?;
+            let _flags = reader.u32()?;
+            let net_name_offset = reader.u32()? as usize;
+            let _device_name_offset = reader.u32()?;
+            let _provider_type = reader.u32()?;
+
+            let net_name = if net_name_offset > 0x14 {
+                let net_name_unicode = reader.u32()? as usize;
+                utf16_cstr_at(link, net_name_unicode)
+            } else {
+                ansi_cstr_at(link, net_name_offset)
+            };
+
+            if let Some(net_name) = net_name.filter(|n| !n.is_empty()) {
+                self.network_path = Some(if suffix.is_empty() {
+                    net_name
+                } else {
+                    format!("{}\\{}", net_name.trim_end_matches('\\'), suffix)
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    // Best file system target for the shortcut, with environment variables expanded.
+    // Mirrors the order the shell itself prefers: the environment block (written for
+    // targets under %ProgramFiles% etc.), then LinkInfo, then the IDList.
+    pub fn target_path(&self) -> Option<String> {
+        if let Some(target) = self.environment_target.as_deref().filter(|t| !t.is_empty()) {
+            return Some(expand_env_vars(target));
+        }
+        if let Some(path) = &self.local_base_path {
+            return Some(path.clone());
+        }
+        if let Some(path) = &self.network_path {
+            return Some(path.clone());
+        }
+        if let (Some(id), Some(suffix)) = (&self.known_folder_id, &self.known_folder_path) {
+            if let Some((_, folder)) = KNOWN_FOLDERS.iter().find(|(guid, _)| guid == id) {
+                let folder = expand_env_vars(folder);
+                return Some(if suffix.is_empty() {
+                    folder
+                } else {
+                    format!("{}\\{}", folder, suffix)
+                });
+            }
+        }
+        self.id_list_path.clone()
+    }
+
+    // Like target_path, but falls back to the RelativePath string (relative to the
+    // shortcut's own folder) when no absolute target is recorded
+    pub fn resolve_target(&self, link_path: &Path) -> Option<String> {
+        if let Some(target) = self.target_path() {
+            return Some(target);
+        }
+
+        let relative = self.relative_path.as_ref()?;
+        let parent = link_path.parent()?;
+        Some(parent.join(relative).to_string_lossy().into_owned())
+    }
+
+    // Icon file from the icon environment block or the IconLocation string
+    pub fn icon_path(&self) -> Option<String> {
+        self.icon_environment_target.as_deref()
+            .filter(|t| !t.is_empty())
+            .or(self.icon_location.as_deref().filter(|t| !t.is_empty()))
+            .map(expand_env_vars)
+    }
+}
+
+// Resolve a .lnk file to its target
+pub fn resolve_shortcut(path: &Path) -> Result<String, String> {
+    ShellLink::open(path)?
+        .resolve_target(path)
+        .ok_or_else(|| format!("Shortcut has no resolvable target: {}", path.display()))
+}
+
+// Expand %VAR% references, leaving unknown variables untouched like ExpandEnvironmentStrings
+pub fn expand_env_vars(input: &str) -> String {
+    let mut result = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find('%') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) if end > 0 => {
+                let name = &after[..end];
+                match std::env::var(name) {
+                    Ok(value) => result.push_str(&value),
+                    Err(_) => {
+                        result.push('%');
+                        result.push_str(name);
+                        result.push('%');
+                    }
+                }
+                rest = &after[end + 1..];
+            }
+            _ => {
+                result.push('%');
+                rest = after;
+            }
+        }
+    }
+
+    result.push_str(rest);
+    result
+}
+
+// HotKeyFlags: virtual key code in the low byte, modifiers in the high byte
+const HOTKEYF_SHIFT: u8 = 0x01;
+const HOTKEYF_CONTROL: u8 = 0x02;
+const HOTKEYF_ALT: u8 = 0x04;
+
+// Human-readable form of a HotKey such as "Ctrl+Alt+K", or None if unset or the key
+// isn't one a shortcut can use
+pub fn format_hotkey(hotkey: u16) -> Option<String> {
+    let [key, modifiers] = hotkey.to_le_bytes();
+    let key = match key {
+        b'0'..=b'9' | b'A'..=b'Z' => (key as char).to_string(),
+        0x70..=0x87 => format!("F{}", key - 0x6F),
+        0x90 => "NumLock".to_string(),
+        0x91 => "ScrollLock".to_string(),
+        _ => return None,
+    };
+
+    let mut parts: Vec<String> = [(HOTKEYF_CONTROL, "Ctrl"), (HOTKEYF_ALT, "Alt"), (HOTKEYF_SHIFT, "Shift")]
+        .iter()
+        .filter(|(flag, _)| modifiers & flag != 0)
+        .map(|(_, name)| name.to_string())
+        .collect();
+    parts.push(key);
+    Some(parts.join("+"))
+}
+
+fn parse_id_list(data: &[u8]) -> Vec<&[u8]> {
+    let mut items = Vec::new();
+    let mut pos = 0;
+
+    while pos + 2 <= data.len() {
+        let size = u16::from_le_bytes([data[pos], data[pos + 1]]) as usize;
+        if size < 2 || pos + size > data.len() {
+            break;
+        }
+        items.push(&data[pos + 2..pos + size]);
+        pos += size;
+    }
+
+    items
+}
+
+// Build a path from the shell items, if they start at My Computer or a drive
+fn id_list_path(items: &[&[u8]]) -> Option<String> {
+    let mut path = String::new();
+
+    for item in items {
+        let Some(&class) = item.first() else { continue };
+        match class {
+            // Root folder, only My Computer leads to a file system path
+            0x1F => {
+                if item.len() < 18 || format_guid(&item[2..18]) != CLSID_MY_COMPUTER {
+                    return None;
+                }
+            }
+            // Volume ("C:\")
+            0x20..=0x2F => {
+                path = ansi_cstr_at(item, 1)?;
+            }
+            // File or directory entry
+            0x30..=0x3F => {
+                let name = file_entry_name(item)?;
+                if path.is_empty() {
+                    return None;
+                }
+                if !path.ends_with('\\') {
+                    path.push('\\');
+                }
+                path.push_str(&name);
+            }
+            _ => return None,
+        }
+    }
+
+    if path.is_empty() { None } else { Some(path) }
+}
+
+// Path below a known folder; `offset` is where the folder's first child item starts in the IDList
+fn known_folder_suffix(items: &[&[u8]], offset: usize) -> Option<String> {
+    let mut pos = 0;
+    let mut names = Vec::new();
+
+    for item in items {
+        if pos >= offset {
+            names.push(file_entry_name(item)?);
+        }
+        pos += item.len() + 2;
+    }
+
+    Some(names.join("\\"))
+}
+
+fn file_entry_name(item: &[u8]) -> Option<String> {
+    if item.len() < 12 || !(0x30..=0x3F).contains(&item[0]) {
+        return None;
+    }
+
+    // Prefer the long name from the 0xBEEF0004 extension block
+    if let Some(name) = file_entry_long_name(item) {
+        return Some(name);
+    }
+
+    // Short name follows: class, unknown, size u32, modified u32, attributes u16
+    if item[0] & 0x04 != 0 {
+        utf16_cstr_at(item, 12)
+    } else {
+        ansi_cstr_at(item, 12)
+    }
+}
+
+fn file_entry_long_name(item: &[u8]) -> Option<String> {
+    // The last u16 of the item holds the offset of the first extension block
+    if item.len() < 4 {
+        return None;
+    }
+    let offset = u16::from_le_bytes([item[item.len() - 2], item[item.len() - 1]]) as usize;
+    // Offsets are relative to the item including its u16 size prefix
+    let start = offset.checked_sub(2)?;
+    let ext = item.get(start..)?;
+    if ext.len() < 8 {
+        return None;
+    }
+
+    let version = u16::from_le_bytes([ext[2], ext[3]]);
+    let signature = u32::from_le_bytes([ext[4], ext[5], ext[6], ext[7]]);
+    if signature != FILE_ENTRY_EXTENSION_SIGNATURE {
+        return None;
+    }
+
+    let name_offset = match version {
+        0..=2 => return None,
+        3..=6 => 20,
+        7 => 38,
+        8 => 42,
+        _ => 46,
+    };
+
+    utf16_cstr_at(ext, name_offset).filter(|n| !n.is_empty())
+}
+
+fn environment_block_target(body: &[u8]) -> Option<String> {
+    // TargetAnsi[260] followed by TargetUnicode[520]
+    let unicode = body.get(260..780).and_then(|b| utf16_cstr_at(b, 0));
+    unicode
+        .filter(|t| !t.is_empty())
+        .or_else(|| body.get(..260).and_then(|b| ansi_cstr_at(b, 0)))
+        .filter(|t| !t.is_empty())
+}
+
+fn format_guid(bytes: &[u8]) -> String {
+    format!(
+        "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{:02X}{:02X}{:02X}{:02X}{:02X}{:02X}",
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        u16::from_le_bytes([bytes[6], bytes[7]]),
+        bytes[8], bytes[9], bytes[10], bytes[11], bytes[12], bytes[13], bytes[14], bytes[15]
+    )
+}
+
+// ANSI strings are decoded as Latin-1, which is lossless for the ASCII paths
+// shortcuts almost always contain and never fails.
+fn ansi_cstr_at(data: &[u8], offset: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    Some(bytes[..end].iter().map(|&b| b as char).collect())
+}
+
+fn utf16_cstr_at(data: &[u8], offset: usize) -> Option<String> {
+    let bytes = data.get(offset..)?;
+    let units: Vec<u16> = bytes.chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    Some(String::from_utf16_lossy(&units))
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self.pos.checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| "Unexpected end of shell link data".to_string())?;
+        let slice = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Result<(), String> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        let b = self.bytes(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        let b = self.bytes(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    // StringData entries are a u16 character count followed by the characters
+    fn string_data(&mut self, unicode: bool) -> Result<String, String> {
+        let count = self.u16()? as usize;
+        if unicode {
+            let bytes = self.bytes(count * 2)?;
+            let units: Vec<u16> = bytes.chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+            Ok(String::from_utf16_lossy(&units))
+        } else {
+            Ok(self.bytes(count)?.iter().map(|&b| b as char).collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(data: &[u8]) -> ShellLink {
+        ShellLink::parse(data).unwrap()
+    }
+
+    #[test]
+    fn local_target_with_string_data() {
+        let link = fixture(include_bytes!("../tests/fixtures/lnk/local.lnk"));
+
+        assert_eq!(link.local_base_path.as_deref(), Some("C:\\Program Files\\Example App\\example.exe"));
+        assert_eq!(link.id_list_path.as_deref(), Some("C:\\Program Files\\Example App\\example.exe"));
+        assert_eq!(link.network_path, None);
+        assert_eq!(link.name.as_deref(), Some("Example App – Stable"));
+        assert_eq!(link.relative_path.as_deref(), Some("..\\..\\Program Files\\Example App\\example.exe"));
+        assert_eq!(link.working_dir.as_deref(), Some("C:\\Program Files\\Example App"));
+        assert_eq!(link.arguments.as_deref(), Some("--profile \"Work Profile\" --new-window"));
+        assert_eq!(link.icon_location.as_deref(), Some("C:\\Program Files\\Example App\\example.ico"));
+        assert_eq!(link.icon_index, 2);
+        assert_eq!(link.show_command, 3);
+        assert_eq!(link.file_size, 4096);
+        assert_eq!(format_hotkey(link.hotkey).as_deref(), Some("Ctrl+Alt+K"));
+        assert_eq!(link.target_path().as_deref(), Some("C:\\Program Files\\Example App\\example.exe"));
+        assert_eq!(link.icon_path().as_deref(), Some("C:\\Program Files\\Example App\\example.ico"));
+    }
+
+    #[test]
+    fn network_target_with_ansi_strings() {
+        let link = fixture(include_bytes!("../tests/fixtures/lnk/network.lnk"));
+
+        assert_eq!(link.local_base_path, None);
+        assert_eq!(link.network_path.as_deref(), Some("\\\\fileserver\\tools\\bin\\tool.exe"));
+        assert_eq!(link.working_dir.as_deref(), Some("\\\\fileserver\\tools\\bin"));
+        assert_eq!(link.arguments.as_deref(), Some("/quiet"));
+        assert_eq!(link.show_command, 7);
+        assert_eq!(link.target_path().as_deref(), Some("\\\\fileserver\\tools\\bin\\tool.exe"));
+    }
+
+    #[test]
+    fn environment_blocks_take_precedence() {
+        let link = fixture(include_bytes!("../tests/fixtures/lnk/environment.lnk"));
+
+        assert_eq!(link.environment_target.as_deref(), Some("%ProgramFiles%\\Vendor\\tool.exe"));
+        assert_eq!(link.icon_environment_target.as_deref(), Some("%SystemRoot%\\System32\\shell32.dll"));
+        assert_eq!(link.local_base_path.as_deref(), Some("C:\\Program Files\\Vendor\\tool.exe"));
+        assert_eq!(link.target_path(), Some(expand_env_vars("%ProgramFiles%\\Vendor\\tool.exe")));
+        assert_eq!(link.icon_path(), Some(expand_env_vars("%SystemRoot%\\System32\\shell32.dll")));
+    }
+
+    #[test]
+    fn known_folder_target() {
+        let link = fixture(include_bytes!("../tests/fixtures/lnk/known_folder.lnk"));
+
+        assert_eq!(link.known_folder_id.as_deref(), Some("905E63B6-C1BF-494E-B29C-65B732D3D21A"));
+        assert_eq!(link.known_folder_path.as_deref(), Some("Vendor\\tool.exe"));
+        assert_eq!(link.local_base_path, None);
+        assert_eq!(link.target_path(), Some(format!("{}\\Vendor\\tool.exe", expand_env_vars("%ProgramFiles%"))));
+    }
+
+    #[test]
+    fn relative_path_resolves_against_the_link() {
+        let link = fixture(include_bytes!("../tests/fixtures/lnk/relative.lnk"));
+
+        assert_eq!(link.target_path(), None);
+        let resolved = link.resolve_target(Path::new("/links/Apps/portable.lnk")).unwrap();
+        assert_eq!(Path::new(&resolved), Path::new("/links/Apps").join("..\\Tools\\portable.exe"));
+    }
+
+    #[test]
+    fn rejects_truncated_and_foreign_data() {
+        let data = include_bytes!("../tests/fixtures/lnk/local.lnk");
+        assert!(ShellLink::parse(&data[..0x60]).is_err());
+        assert!(ShellLink::parse(b"[InternetShortcut]\r\nURL=https://example.com\r\n").is_err());
+    }
+}
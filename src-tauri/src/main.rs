@@ -3,420 +3,162 @@
 
 use tauri::{Manager, PhysicalSize, Size, SystemTray, SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem};
 use window_shadows::set_shadow;
+#[cfg(target_os = "windows")]
 use window_vibrancy::apply_blur;
+#[cfg(windows)]
 use winreg::enums::*;
+#[cfg(windows)]
 use winreg::RegKey;
 use std::fs;
 use std::path::Path;
-use serde::{Deserialize, Serialize};
 use base64::{engine::general_purpose::STANDARD, Engine};
-use windows::Win32::UI::Shell::{
-    SHFILEINFOW, SHGFI_ICON, SHGFI_LARGEICON, SHGetFileInfoW,
-    ShellLink, IShellLinkW
-};
-use windows::Win32::System::Com::{
-    CoCreateInstance, CLSCTX_INPROC_SERVER, STGM_READ,
-    IPersistFile
-};
-use windows::Win32::UI::WindowsAndMessaging::{
-    DestroyIcon, DrawIconEx, HICON, DI_NORMAL
-};
-use windows::Win32::Graphics::Gdi::{
-    CreateCompatibleBitmap, CreateCompatibleDC, DeleteDC, DeleteObject,
-    GetDC, ReleaseDC, SelectObject, GetBitmapBits, FillRect, GetStockObject,
-    BLACK_BRUSH, HBRUSH
-};
-use windows::core::{ComInterface, PCWSTR};
 use std::fs::OpenOptions;
 use std::io::Write;
 use image;
-use image::ImageEncoder;
-use windows::Win32::UI::Shell::ShellExecuteW;
-use windows::core::HSTRING;
-use windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES;
+#[cfg(windows)]
 use windows::Win32::System::Com::{
     CoInitializeEx, COINIT_APARTMENTTHREADED
 };
-use windows::Win32::Storage::FileSystem::WIN32_FIND_DATAW;
 use md5;
 use url;
 
+mod app_index;
 mod app_manager;
+#[cfg(target_os = "linux")]
+mod desktop_entry;
+mod discovery;
+mod health;
+mod ico;
+mod icon_cache;
+mod icon_protocol;
+#[cfg(target_os = "linux")]
+mod icon_theme;
+mod icons;
+mod internet_shortcut;
+mod launcher;
+mod lnk;
+mod settings;
+#[cfg(windows)]
+mod shell_icons;
+mod sources;
+mod usage;
 use app_manager::AppManager;
+use discovery::{AppIndexStats, AppInfo, ScanDone};
+use health::BrokenApp;
+use icon_cache::{IconCache, PruneResult};
+use icons::{IconBatchSummary, IconResult};
+use settings::{AppSettings, LaunchProfile, ScanRoot};
+use usage::{RankedApp, UsageStore};
+use launcher::{LaunchError, LaunchRequest, Launcher, SystemLauncher};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-struct AppInfo {
-    name: String,
-    path: String,
-    icon: Option<String>,
-    category: String,
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-struct AppSettings {
-    custom_icons: std::collections::HashMap<String, String>,
-    moved_apps: std::collections::HashMap<String, String>,
-    pinned_apps: Vec<String>,
-    recent_apps: Vec<String>,
-    is_grid_view: bool,
-    categories: std::collections::HashMap<String, String>,
+#[tauri::command]
+async fn refresh_start_menu_apps(app_handle: tauri::AppHandle) -> Result<Vec<AppInfo>, String> {
+    scan_apps(app_handle, true).await
 }
 
-// Memory cache for app scanning results
-static APP_CACHE: std::sync::OnceLock<std::sync::Mutex<Option<(Vec<AppInfo>, std::time::Instant)>>> = std::sync::OnceLock::new();
-
 #[tauri::command]
-async fn refresh_start_menu_apps() -> Result<Vec<AppInfo>, String> {
-    // Initialize the cache if it hasn't been initialized yet
-    let cache = APP_CACHE.get_or_init(|| {
-        std::sync::Mutex::new(None)
-    });
-    
-    // Explicitly clear the cache to force a fresh scan
-    {
-        let mut cache_guard = cache.lock().unwrap();
-        *cache_guard = None;
-    }
-    
-    // Call get_start_menu_apps to perform a fresh scan
-    get_start_menu_apps().await
+async fn get_start_menu_apps(app_handle: tauri::AppHandle) -> Result<Vec<AppInfo>, String> {
+    scan_apps(app_handle, false).await
 }
 
-#[tauri::command]
-async fn get_start_menu_apps() -> Result<Vec<AppInfo>, String> {
-    // Initialize the cache if it hasn't been initialized yet
-    let cache = APP_CACHE.get_or_init(|| {
-        std::sync::Mutex::new(None)
-    });
-    
-    // Try to get cached results first
-    {
-        let cache_guard = cache.lock().unwrap();
-        if let Some((cached_apps, timestamp)) = &*cache_guard {
-            // Cache is valid for 5 minutes
-            if timestamp.elapsed() < std::time::Duration::from_secs(300) {
-                log_error("Using cached app list");
-                return Ok(cached_apps.clone());
-            }
-        }
-    }
-    
-    log_error("Scanning start menu apps (cache expired or not found)");
-    let mut apps = Vec::new();
-    
-    // First, clear the cache to ensure fresh scan
-    let cache = APP_CACHE.get_or_init(|| {
-        std::sync::Mutex::new(None)
-    });
-    {
-        let mut cache_guard = cache.lock().unwrap();
-        *cache_guard = None;
-    }
-    
-    // Common Start Menu paths
-    let paths = vec![
-        std::env::var("ProgramData").unwrap_or_default() + "\\Microsoft\\Windows\\Start Menu\\Programs",
-        std::env::var("APPDATA").unwrap_or_default() + "\\Microsoft\\Windows\\Start Menu\\Programs",
-    ];
-
-    // Log number of paths
-    log_error(&format!("Scanning {} paths", paths.len()));
-    
-    // Process each path sequentially for more reliable results
-    for path in &paths {
-        log_error(&format!("Scanning path: {}", path));
-        let mut path_apps = Vec::new();
-        if scan_directory(&Path::new(&path), &mut path_apps).is_ok() {
-            log_error(&format!("Found {} apps in {}", path_apps.len(), path));
-            apps.extend(path_apps);
-        }
-    }
-    
-    log_error(&format!("Total apps found: {}", apps.len()));
-    
-    // Update cache
-    {
-        let mut cache_guard = cache.lock().unwrap();
-        *cache_guard = Some((apps.clone(), std::time::Instant::now()));
-    }
+// Run the scan off the command thread, reporting scan://progress while it walks and
+// scan://done when it finishes or fails
+async fn scan_apps(app_handle: tauri::AppHandle, force_refresh: bool) -> Result<Vec<AppInfo>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let progress_handle = app_handle.clone();
+        let manager = app_handle.state::<AppManager>();
+        let result = manager.apps(force_refresh, &move |progress| {
+            let _ = progress_handle.emit_all("scan://progress", progress);
+        });
 
-    Ok(apps)
-}
+        let done = match &result {
+            Ok(apps) => ScanDone { apps_found: apps.len(), error: None },
+            Err(e) => ScanDone { apps_found: 0, error: Some(e.clone()) },
+        };
+        let _ = app_handle.emit_all("scan://done", done);
 
-fn scan_directory(dir: &Path, apps: &mut Vec<AppInfo>) -> Result<(), String> {
-    // Log directory scanning
-    log_error(&format!("Scanning directory: {}", dir.display()));
-    
-    // Skip only very specific system directories
-    let dir_name = dir.file_name().map(|n| n.to_string_lossy().to_lowercase());
-    if let Some(name) = dir_name {
-        // Skip only Windows system directories by exact match
-        let skip_dirs = [
-            "windows", "system32", "systemapps", "syswow64",
-            "winsxs", "assembly", "microsoft.net"
-        ];
-        
-        if skip_dirs.iter().any(|&skip| name == skip) {
-            log_error(&format!("Skipping system directory: {}", dir.display()));
-            return Ok(());
-        }
-    }
-    
-    // No maximum entry limit - scan all entries
-    
-    if let Ok(entries) = fs::read_dir(dir) {
-        for entry in entries.flatten() {
-            let path = entry.path();
-            
-            if path.is_dir() {
-                // Recursively scan subdirectory with more permissive rules
-                scan_directory(&path, apps)?;
-            } else if let Some(ext) = path.extension() {
-                let ext_str = ext.to_string_lossy().to_lowercase();
-                // Process both .lnk and .url files
-                if ext_str == "lnk" || ext_str == "url" {
-                    if let Some(app_info) = create_app_info(&path) {
-                        log_error(&format!("Found app: {}", app_info.name));
-                        apps.push(app_info);
-                    }
-                }
-            }
+        if settings::load_or_default().hide_broken_apps {
+            return result.map(health::without_broken);
         }
-    }
-    Ok(())
+        result
+    })
+    .await
+    .map_err(|e| format!("Scan task failed: {}", e))?
 }
 
-fn create_app_info(path: &Path) -> Option<AppInfo> {
-    let name = path.file_stem()?.to_string_lossy().into_owned();
-    let path_str = path.to_string_lossy().into_owned();
-    
-    // Basic category detection based on path
-    let category = if path_str.contains("Games") {
-        "Games"
-    } else if path_str.contains("Accessories") || path_str.contains("System Tools") {
-        "Utilities"
-    } else if path_str.contains("Media") {
-        "Media"
-    } else if path_str.contains("Development") || path_str.contains("Programming") {
-        "Development"
-    } else {
-        "Other"
-    }.to_string();
-
-    Some(AppInfo {
-        name,
-        path: path_str,
-        icon: None,
-        category,
+// Indexed entries that can't be launched, e.g. shortcuts left behind by an uninstall
+#[tauri::command]
+async fn get_broken_apps(app_handle: tauri::AppHandle) -> Result<Vec<BrokenApp>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let manager = app_handle.state::<AppManager>();
+        let apps = manager.apps(false, &|_| {})?;
+        Ok(health::check(&apps))
     })
+    .await
+    .map_err(|e| format!("Health check failed: {}", e))?
 }
 
 #[tauri::command]
-async fn get_app_icon(path: String) -> Result<String, String> {
-    get_app_icon_internal(&path)
+async fn get_app_index_stats(manager: tauri::State<'_, AppManager>) -> Result<AppIndexStats, String> {
+    Ok(manager.stats())
 }
 
-fn get_app_icon_internal(path: &str) -> Result<String, String> {
-    unsafe {
-        let path_wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
-        let mut file_info: SHFILEINFOW = std::mem::zeroed();
-        
-        // Try with different flag combinations
-        let flags = SHGFI_ICON | SHGFI_LARGEICON;
-        
-        let result = SHGetFileInfoW(
-            PCWSTR(path_wide.as_ptr()),
-            FILE_FLAGS_AND_ATTRIBUTES(0),
-            Some(&mut file_info),
-            std::mem::size_of::<SHFILEINFOW>() as u32,
-            flags
-        );
-
-        if result == 0 || file_info.hIcon.is_invalid() {
-            // If first attempt fails, handle different file types
-            let path_lower = path.to_lowercase();
-            
-            if path_lower.ends_with(".lnk") {
-                // For .lnk files, get icon from target
-                if let Ok(target_path) = resolve_shortcut(path) {
-                    return get_app_icon_internal(&target_path);
-                }
-            } else if path_lower.ends_with(".url") {
-                // For .url files, use default browser icon or parse the URL file
-                log_error(&format!("Getting icon for URL file: {}", path));
-                
-                // Try to read the URL file to extract the URL
-                if let Ok(content) = std::fs::read_to_string(path) {
-                    // Look for the URL= line in the file
-                    if let Some(url_line) = content.lines().find(|line| line.starts_with("URL=")) {
-                        let url = url_line.trim_start_matches("URL=").trim();
-                        
-                        // Try to get favicon using the domain's favicon.ico
-                        if url.starts_with("http") {
-                            if let Ok(url) = url::Url::parse(url) {
-                                if let Some(_domain) = url.host_str() {
-                                    // Use browser icon as fallback
-                                    let browsers = [
-                                        "C:\\Program Files\\Google\\Chrome\\Application\\chrome.exe",
-                                        "C:\\Program Files (x86)\\Microsoft\\Edge\\Application\\msedge.exe",
-                                        "C:\\Program Files\\Mozilla Firefox\\firefox.exe"
-                                    ];
-                                    
-                                    for browser in browsers {
-                                        if std::path::Path::new(browser).exists() {
-                                            return get_app_icon_internal(browser);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-                
-                // Default to Edge browser icon if all else fails
-                let edge_path = "C:\\Program Files (x86)\\Microsoft\\Edge\\Application\\msedge.exe";
-                if std::path::Path::new(edge_path).exists() {
-                    return get_app_icon_internal(edge_path);
-                }
-            }
-            
-            return Err("Failed to get icon".into());
-        }
-
-        // Add a small delay to ensure icon is fully loaded
-        std::thread::sleep(std::time::Duration::from_millis(50));
-
-        let bitmap = icon_to_bitmap(file_info.hIcon)?;
-        let base64 = STANDARD.encode(&bitmap);
-        
-        if let Err(e) = DestroyIcon(file_info.hIcon) {
-            log_error(&format!("Failed to destroy icon: {:?}", e));
-        }
-        
-        Ok(format!("data:image/png;base64,{}", base64))
-    }
+// Extraction and the cache index write block, so they run off the async runtime
+#[tauri::command]
+async fn get_app_icon(app_handle: tauri::AppHandle, path: String) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let manager = app_handle.state::<AppManager>();
+        let icons = app_handle.state::<IconCache>();
+        icons::load_icon(&path, icons::DEFAULT_ICON_SIZE, &manager, &icons)
+    })
+    .await
+    .map_err(|e| format!("Icon task failed: {}", e))?
 }
 
-fn icon_to_bitmap(hicon: HICON) -> Result<Vec<u8>, String> {
-    unsafe {
-        let hdc = GetDC(None);
-        let hdcmem = CreateCompatibleDC(hdc);
-        
-        // Increase size for better quality
-        let size: u32 = 128;  // Try a larger size
-        let hbitmap = CreateCompatibleBitmap(hdc, size as i32, size as i32);
-        let holdbitmap = SelectObject(hdcmem, hbitmap);
-        
-        let rect = windows::Win32::Foundation::RECT {
-            left: 0,
-            top: 0,
-            right: size as i32,
-            bottom: size as i32,
-        };
-        
-        let brush = GetStockObject(BLACK_BRUSH);
-        FillRect(
-            hdcmem,
-            &rect,
-            HBRUSH(brush.0)
-        );
-        
-        // Draw the icon with better quality
-        if let Err(e) = DrawIconEx(
-            hdcmem,
-            0,
-            0,
-            hicon,
-            size as i32,
-            size as i32,
-            0,
-            None,
-            DI_NORMAL
-        ) {
-            log_error(&format!("Failed to draw icon: {:?}", e));
-            return Err("Failed to draw icon".into());
-        }
-        
-        let mut bits = vec![0u8; (size * size * 4) as usize];
-        GetBitmapBits(hbitmap, bits.len() as i32, bits.as_mut_ptr() as *mut _);
-        
-        // Fix color channels and handle alpha
-        for pixel in bits.chunks_exact_mut(4) {
-            pixel.swap(0, 2);
-            
-            // If pixel is black (background), make it transparent
-            if pixel[0] == 0 && pixel[1] == 0 && pixel[2] == 0 {
-                pixel[3] = 0;
-            } else {
-                pixel[3] = 255;  // Full opacity for non-background pixels
-            }
-        }
-        
-        // Create high-quality PNG
-        let img = image::RgbaImage::from_raw(size, size, bits)
-            .ok_or_else(|| "Failed to create image".to_string())?;
-        
-        let mut png_data = Vec::new();
-        let mut cursor = std::io::Cursor::new(&mut png_data);
-        
-        let encoder = image::codecs::png::PngEncoder::new_with_quality(
-            &mut cursor,
-            image::codecs::png::CompressionType::Best,
-            image::codecs::png::FilterType::Adaptive
-        );
-        
-        encoder.write_image(
-            img.as_raw(),
-            size,
-            size,
-            image::ColorType::Rgba8
-        ).map_err(|e| e.to_string())?;
-        
-        // Cleanup
-        SelectObject(hdcmem, holdbitmap);
-        DeleteObject(hbitmap);
-        DeleteDC(hdcmem);
-        ReleaseDC(None, hdc);
-        
-        Ok(png_data)
-    }
+// Icons for many entries at once. Each result is emitted as an icons://loaded event as
+// soon as it is ready; the command resolves once all of them have been sent.
+#[tauri::command]
+async fn get_app_icons(
+    app_handle: tauri::AppHandle,
+    paths: Vec<String>,
+    size: Option<u32>,
+) -> Result<IconBatchSummary, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let manager = app_handle.state::<AppManager>();
+        let icons = app_handle.state::<IconCache>();
+        let emit_handle = app_handle.clone();
+
+        icons::load_icons(
+            &paths,
+            size.unwrap_or(icons::DEFAULT_ICON_SIZE),
+            &manager,
+            &icons,
+            &move |result: IconResult| {
+                if let Err(e) = emit_handle.emit_all("icons://loaded", result) {
+                    log_error(&format!("Failed to emit icon result: {}", e));
+                }
+            },
+        )
+    })
+    .await
+    .map_err(|e| format!("Icon task failed: {}", e))
 }
 
-fn resolve_shortcut(path: &str) -> Result<String, String> {
-    unsafe {
-        let shell_link: IShellLinkW = CoCreateInstance(
-            &ShellLink,
-            None,
-            CLSCTX_INPROC_SERVER
-        ).map_err(|e| e.to_string())?;
-
-        let persist_file: IPersistFile = shell_link.cast()
-            .map_err(|e| e.to_string())?;
-        
-        let path_wide: Vec<u16> = path.encode_utf16()
-            .chain(std::iter::once(0))
-            .collect();
-        
-        persist_file.Load(PCWSTR(path_wide.as_ptr()), STGM_READ)
-            .map_err(|e| e.to_string())?;
-        
-        let mut target_path = [0u16; 260];
-        let mut find_data = WIN32_FIND_DATAW::default();
-        
-        shell_link.GetPath(
-            &mut target_path,
-            &mut find_data,
-            0
-        ).map_err(|e| e.to_string())?;
-        
-        Ok(String::from_utf16_lossy(
-            &target_path[..target_path.iter().position(|&x| x == 0).unwrap_or(260)]
-        ))
-    }
+// Walks and deletes cache files, so it runs off the async runtime
+#[tauri::command]
+async fn prune_icon_cache(app_handle: tauri::AppHandle, max_bytes: Option<u64>) -> Result<PruneResult, String> {
+    tauri::async_runtime::spawn_blocking(move || app_handle.state::<IconCache>().prune(max_bytes))
+        .await
+        .map_err(|e| format!("Icon cache prune failed: {}", e))?
 }
 
 #[tauri::command]
 async fn get_system_accent_color() -> Result<String, String> {
+    system_accent_color()
+}
+
+#[cfg(windows)]
+fn system_accent_color() -> Result<String, String> {
     let hkcu = RegKey::predef(HKEY_CURRENT_USER);
     let dwm_key = hkcu.open_subkey("SOFTWARE\\Microsoft\\Windows\\DWM")
         .map_err(|e| e.to_string())?;
@@ -427,30 +169,129 @@ async fn get_system_accent_color() -> Result<String, String> {
     Ok(format!("#{:06X}", accent_color & 0xFFFFFF))
 }
 
+#[cfg(not(windows))]
+fn system_accent_color() -> Result<String, String> {
+    Err("The system accent color is only available on Windows".into())
+}
+
 
+// `source` names the entry point (e.g. "app_list" or "search") for the usage store
 #[tauri::command]
-async fn launch_app(path: String) -> Result<(), String> {
-    unsafe {
-        let path_wide = HSTRING::from(path);
-        let operation = HSTRING::from("open");
-        
-        let result = ShellExecuteW(
-            None,
-            &operation,
-            &path_wide,
-            None,
-            None,
-            windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL
-        );
-        
-        if result.0 <= 32 {
-            return Err("Failed to launch application".into());
+async fn launch_app(
+    path: String,
+    source: Option<String>,
+    manager: tauri::State<'_, AppManager>,
+    usage: tauri::State<'_, UsageStore>,
+    launcher: tauri::State<'_, Box<dyn Launcher>>,
+) -> Result<(), LaunchError> {
+    let app = manager.find(&path);
+
+    match app.as_ref().and_then(|app| sources::source_for(app).map(|source| (app, source))) {
+        Some((app, source)) => source.launch(app, &**launcher),
+        // Not a discovered entry (e.g. a path the frontend kept around), let the shell open it
+        None => launcher.open(&LaunchRequest::new(&path, None, None, None)),
+    }?;
+
+    record_launch(&usage, app.as_ref(), &path, source.as_deref().unwrap_or("launch_app"));
+    Ok(())
+}
+
+// Launch an entry with one of the named profiles stored for it in settings
+#[tauri::command]
+async fn launch_app_with_profile(
+    path: String,
+    profile: String,
+    manager: tauri::State<'_, AppManager>,
+    usage: tauri::State<'_, UsageStore>,
+    launcher: tauri::State<'_, Box<dyn Launcher>>,
+) -> Result<(), LaunchError> {
+    let settings = settings::load()?;
+    let launch_profile = settings.launch_profile(&path, &profile)
+        .ok_or_else(|| format!("No launch profile named {} for {}", profile, path))?;
+
+    let app = manager.find(&path);
+    match &app {
+        Some(app) => match sources::source_for(app) {
+            Some(source) => source.launch_with_profile(app, launch_profile, &**launcher),
+            None => launcher.open(&LaunchRequest::with_profile(app, launch_profile)),
+        },
+        None => {
+            let app = AppInfo { path: path.clone(), ..Default::default() };
+            launcher.open(&LaunchRequest::with_profile(&app, launch_profile))
         }
-        
-        Ok(())
+    }?;
+
+    record_launch(&usage, app.as_ref(), &path, &format!("profile:{}", launch_profile.name));
+    Ok(())
+}
+
+// Launches are counted under the entry's own path, whichever of its shortcuts was used
+fn record_launch(usage: &UsageStore, app: Option<&AppInfo>, path: &str, source: &str) {
+    let path = app.map(|app| app.path.as_str()).unwrap_or(path);
+    if let Err(e) = usage.record(path, source) {
+        log_error(&format!("Failed to record launch of {}: {}", path, e));
     }
 }
 
+// Apps matching `query` by name, most frecent first
+#[tauri::command]
+async fn get_ranked_apps(query: Option<String>, app_handle: tauri::AppHandle) -> Result<Vec<RankedApp>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let manager = app_handle.state::<AppManager>();
+        let usage = app_handle.state::<UsageStore>();
+        let mut apps = manager.apps(false, &|_| {})?;
+        if settings::load_or_default().hide_broken_apps {
+            apps = health::without_broken(apps);
+        }
+        Ok(usage.rank(apps, query.as_deref().unwrap_or_default()))
+    })
+    .await
+    .map_err(|e| format!("Ranking failed: {}", e))?
+}
+
+#[tauri::command]
+async fn list_launch_profiles(path: String) -> Result<Vec<LaunchProfile>, String> {
+    Ok(settings::load()?.launch_profiles.remove(&path).unwrap_or_default())
+}
+
+// Add a profile, replacing any existing one of the same name
+#[tauri::command]
+async fn save_launch_profile(path: String, profile: LaunchProfile) -> Result<Vec<LaunchProfile>, String> {
+    if profile.name.trim().is_empty() {
+        return Err("Launch profile needs a name".into());
+    }
+    if let Some(dir) = profile.working_dir.as_deref().filter(|dir| !dir.is_empty()) {
+        if !Path::new(dir).is_dir() {
+            return Err(format!("Not a directory: {}", dir));
+        }
+    }
+
+    let mut settings = settings::load()?;
+    let profiles = settings.launch_profiles.entry(path).or_default();
+    profiles.retain(|existing| !existing.name.eq_ignore_ascii_case(&profile.name));
+    profiles.push(profile);
+    let profiles = profiles.clone();
+    settings::save(&settings)?;
+    Ok(profiles)
+}
+
+#[tauri::command]
+async fn remove_launch_profile(path: String, name: String) -> Result<Vec<LaunchProfile>, String> {
+    let mut settings = settings::load()?;
+    let profiles = match settings.launch_profiles.get_mut(&path) {
+        Some(profiles) => {
+            profiles.retain(|existing| !existing.name.eq_ignore_ascii_case(&name));
+            profiles.clone()
+        }
+        None => Vec::new(),
+    };
+    if profiles.is_empty() {
+        settings.launch_profiles.remove(&path);
+    }
+    settings::save(&settings)?;
+    Ok(profiles)
+}
+
 #[tauri::command]
 async fn save_custom_icon(app_path: String, icon_data: String) -> Result<String, String> {
     use image::{ImageFormat, DynamicImage, imageops::FilterType};
@@ -467,8 +308,7 @@ async fn save_custom_icon(app_path: String, icon_data: String) -> Result<String,
     }
 
     // Create a unique filename based on the app path
-    let hash = format!("{:x}", md5::compute(&app_path));
-    let icon_path = custom_icons_dir.join(format!("{}.png", hash));
+    let icon_path = custom_icons_dir.join(format!("{}.png", icon_protocol::custom_icon_name(&app_path)));
 
     // Decode base64 image data
     let icon_bytes = STANDARD
@@ -495,9 +335,8 @@ async fn save_custom_icon(app_path: String, icon_data: String) -> Result<String,
     fs::rename(&temp_path, &icon_path)
         .map_err(|e| format!("Failed to save icon: {}", e))?;
 
-    // Return the processed image as base64 for immediate UI update
-    let base64_result = format!("data:image/png;base64,{}", STANDARD.encode(&output_buffer));
-    Ok(base64_result)
+    // Return a URL to the processed image for immediate UI update
+    Ok(icon_protocol::custom_icon_url(&app_path, &output_buffer))
 }
 
 #[tauri::command]
@@ -515,8 +354,7 @@ async fn save_custom_folder_icon(folder_path: String, icon_data: String) -> Resu
     }
 
     // Create a unique filename based on the folder path
-    let hash = format!("{:x}", md5::compute(&folder_path));
-    let icon_path = custom_icons_dir.join(format!("{}.png", hash));
+    let icon_path = custom_icons_dir.join(format!("{}.png", icon_protocol::custom_icon_name(&folder_path)));
 
     // Decode base64 image data
     let icon_bytes = STANDARD
@@ -543,9 +381,8 @@ async fn save_custom_folder_icon(folder_path: String, icon_data: String) -> Resu
     fs::rename(&temp_path, &icon_path)
         .map_err(|e| format!("Failed to save icon: {}", e))?;
 
-    // Return the processed image as base64 for immediate UI update
-    let base64_result = format!("data:image/png;base64,{}", STANDARD.encode(&output_buffer));
-    Ok(base64_result)
+    // Return a URL to the processed image for immediate UI update
+    Ok(icon_protocol::custom_icon_url(&folder_path, &output_buffer))
 }
 
 #[tauri::command]
@@ -555,8 +392,7 @@ async fn remove_custom_folder_icon(folder_path: String) -> Result<String, String
         .ok_or_else(|| "Failed to get app directory".to_string())?;
     
     let custom_icons_dir = app_data_dir.join("custom_folder_icons");
-    let hash = format!("{:x}", md5::compute(&folder_path));
-    let icon_path = custom_icons_dir.join(format!("{}.png", hash));
+    let icon_path = custom_icons_dir.join(format!("{}.png", icon_protocol::custom_icon_name(&folder_path)));
 
     // Remove the icon file if it exists
     if icon_path.exists() {
@@ -568,14 +404,17 @@ async fn remove_custom_folder_icon(folder_path: String) -> Result<String, String
 }
 
 #[tauri::command]
-async fn remove_custom_icon(app_path: String) -> Result<String, String> {
+async fn remove_custom_icon(
+    app_path: String,
+    manager: tauri::State<'_, AppManager>,
+    icons: tauri::State<'_, IconCache>,
+) -> Result<String, String> {
     // Get the app's data directory
     let app_data_dir = tauri::api::path::app_data_dir(&tauri::Config::default())
         .ok_or_else(|| "Failed to get app directory".to_string())?;
     
     let custom_icons_dir = app_data_dir.join("custom_icons");
-    let hash = format!("{:x}", md5::compute(&app_path));
-    let icon_path = custom_icons_dir.join(format!("{}.png", hash));
+    let icon_path = custom_icons_dir.join(format!("{}.png", icon_protocol::custom_icon_name(&app_path)));
 
     // Remove the icon file if it exists
     if icon_path.exists() {
@@ -584,95 +423,60 @@ async fn remove_custom_icon(app_path: String) -> Result<String, String> {
     }
 
     // Get the original icon
-    get_app_icon_internal(&app_path)
+    icons::load_icon(&app_path, icons::DEFAULT_ICON_SIZE, &manager, &icons)
 }
 
 #[tauri::command]
-async fn shell_open(path: String) -> Result<(), String> {
-    unsafe {
-        let path_wide = HSTRING::from(path);
-        let operation = HSTRING::from("explore");
-        
-        let result = ShellExecuteW(
-            None,
-            &operation,
-            &path_wide,
-            None,
-            None,
-            windows::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL
-        );
-        
-        if result.0 <= 32 {
-            return Err("Failed to open folder".into());
-        }
-        
-        Ok(())
-    }
+async fn shell_open(path: String, launcher: tauri::State<'_, Box<dyn Launcher>>) -> Result<(), LaunchError> {
+    launcher.open_folder(&path)
 }
 
 #[tauri::command]
 async fn load_app_settings() -> Result<AppSettings, String> {
-    let app_dir = tauri::api::path::app_data_dir(&tauri::Config::default())
-        .ok_or_else(|| "Failed to get app directory".to_string())?;
-    
-    let settings_file = app_dir.join("settings.json");
-    log_error(&format!("Loading settings from: {}", settings_file.display()));
-    
-    if !settings_file.exists() {
-        log_error("Settings file does not exist, creating default settings");
-        return Ok(AppSettings {
-            custom_icons: std::collections::HashMap::new(),
-            moved_apps: std::collections::HashMap::new(),
-            pinned_apps: Vec::new(),
-            recent_apps: Vec::new(),
-            is_grid_view: true,
-            categories: std::collections::HashMap::new(),
-        });
-    }
-    
-    let content = fs::read_to_string(&settings_file)
-        .map_err(|e| {
-            log_error(&format!("Failed to read settings: {}", e));
-            format!("Failed to read settings: {}", e)
-        })?;
-    
-    serde_json::from_str(&content)
-        .map_err(|e| {
-            log_error(&format!("Failed to parse settings: {}", e));
-            format!("Failed to parse settings: {}", e)
-        })
+    settings::load()
 }
 
 #[tauri::command]
-async fn save_app_settings(settings: AppSettings) -> Result<(), String> {
-    let app_dir = tauri::api::path::app_data_dir(&tauri::Config::default())
-        .ok_or_else(|| "Failed to get app directory".to_string())?;
-    
-    log_error(&format!("Saving settings to: {}", app_dir.display()));
-    
-    fs::create_dir_all(&app_dir)
-        .map_err(|e| {
-            log_error(&format!("Failed to create app directory: {}", e));
-            format!("Failed to create app directory: {}", e)
-        })?;
-    
-    let settings_file = app_dir.join("settings.json");
-    let content = serde_json::to_string_pretty(&settings)
-        .map_err(|e| {
-            log_error(&format!("Failed to serialize settings: {}", e));
-            format!("Failed to serialize settings: {}", e)
-        })?;
-    
-    fs::write(&settings_file, &content)
-        .map_err(|e| {
-            log_error(&format!("Failed to write settings: {}", e));
-            format!("Failed to write settings: {}", e)
-        })?;
-    
-    log_error(&format!("Successfully saved settings: {}", content));
+async fn save_app_settings(settings: AppSettings, manager: tauri::State<'_, AppManager>) -> Result<(), String> {
+    settings::save(&settings)?;
+
+    // Enabled sources or extra directories may have changed
+    manager.invalidate();
     Ok(())
 }
 
+#[tauri::command]
+async fn list_scan_roots() -> Result<Vec<ScanRoot>, String> {
+    Ok(settings::load()?.scan_roots)
+}
+
+#[tauri::command]
+async fn add_scan_root(root: ScanRoot, manager: tauri::State<'_, AppManager>) -> Result<Vec<ScanRoot>, String> {
+    if !Path::new(&root.path).is_dir() {
+        return Err(format!("Not a directory: {}", root.path));
+    }
+    // Reject bad glob patterns now rather than skipping the root on every scan
+    discovery::ScanRules::from_root(&root)?;
+
+    let mut settings = settings::load()?;
+    settings.scan_roots.retain(|existing| !existing.path.eq_ignore_ascii_case(&root.path));
+    settings.scan_roots.push(root);
+    settings::save(&settings)?;
+
+    manager.invalidate();
+    Ok(settings.scan_roots)
+}
+
+#[tauri::command]
+async fn remove_scan_root(path: String, manager: tauri::State<'_, AppManager>) -> Result<Vec<ScanRoot>, String> {
+    let mut settings = settings::load()?;
+    settings.scan_roots.retain(|existing| !existing.path.eq_ignore_ascii_case(&path));
+    settings::save(&settings)?;
+
+    manager.invalidate();
+    Ok(settings.scan_roots)
+}
+
 // Empty logging function that does nothing - completely eliminates logging
 fn log_error(_error: &str) {
     // No logging at all
@@ -683,6 +487,7 @@ fn main() {
     log_error("Application starting...");
 
     if let Err(e) = std::panic::catch_unwind(|| {
+        #[cfg(windows)]
         unsafe {
             // Try apartment-threaded COM initialization instead
             if let Err(e) = CoInitializeEx(None, COINIT_APARTMENTTHREADED) {
@@ -707,6 +512,11 @@ fn main() {
         
         let app = tauri::Builder::default()
             .system_tray(system_tray)
+            .manage(AppManager::new())
+            .manage(IconCache::new())
+            .manage(UsageStore::new())
+            .manage::<Box<dyn Launcher>>(Box::new(SystemLauncher))
+            .register_uri_scheme_protocol(icon_protocol::SCHEME, icon_protocol::handle)
             .on_system_tray_event(|app, event| match event {
                 SystemTrayEvent::MenuItemClick { id, .. } => {
                     match id.as_str() {
@@ -749,7 +559,7 @@ fn main() {
             }))
             .setup(|app| {
                 let main_window = app.get_window("main").unwrap();
-                let _app_handle = app.handle();
+                let app_handle = app.handle();
                 
                 // Get the current monitor's size
                 if let Some(monitor) = main_window.current_monitor().unwrap() {
@@ -815,8 +625,7 @@ fn main() {
                 window.show().unwrap();
                 window.set_focus().unwrap();
 
-                let app_manager = AppManager::new();
-                app_manager.start_file_watcher();
+                app.state::<AppManager>().start_file_watcher(app_handle);
 
                 log_error("Setup completed successfully");
                 Ok(())
@@ -837,8 +646,14 @@ fn main() {
                 get_start_menu_apps,
                 refresh_start_menu_apps,
                 get_app_icon,
+                get_app_icons,
                 get_system_accent_color,
                 launch_app,
+                launch_app_with_profile,
+                get_ranked_apps,
+                list_launch_profiles,
+                save_launch_profile,
+                remove_launch_profile,
                 save_custom_icon,
                 save_custom_folder_icon,
                 remove_custom_icon,
@@ -846,6 +661,12 @@ fn main() {
                 shell_open,
                 load_app_settings,
                 save_app_settings,
+                list_scan_roots,
+                add_scan_root,
+                remove_scan_root,
+                get_app_index_stats,
+                get_broken_apps,
+                prune_icon_cache,
             ]);
 
         log_error("Starting application...");
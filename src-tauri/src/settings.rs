@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use crate::log_error;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AppSettings {
+    pub custom_icons: HashMap<String, String>,
+    pub moved_apps: HashMap<String, String>,
+    pub pinned_apps: Vec<String>,
+    pub recent_apps: Vec<String>,
+    pub is_grid_view: bool,
+    pub categories: HashMap<String, String>,
+    // Discovery source ids (see sources.rs) the user has switched off
+    #[serde(default)]
+    pub disabled_sources: Vec<String>,
+    // Additional folders scanned by the extra-directories source
+    #[serde(default)]
+    pub scan_roots: Vec<ScanRoot>,
+    // Maximum age of the app index before it is rescanned even though none of the
+    // scanned directories changed; 0 disables the time limit
+    #[serde(default = "default_cache_ttl_secs")]
+    pub cache_ttl_secs: u64,
+    // Size limit of the on-disk icon cache; least recently used icons go first
+    #[serde(default = "default_icon_cache_max_bytes")]
+    pub icon_cache_max_bytes: u64,
+    // Leave entries whose target is gone (see health.rs) out of the app list
+    #[serde(default)]
+    pub hide_broken_apps: bool,
+    // Named launch variants per app, keyed by the app's path
+    #[serde(default)]
+    pub launch_profiles: HashMap<String, Vec<LaunchProfile>>,
+}
+
+// Overrides applied on top of an entry's own launch details by launch_app_with_profile
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct LaunchProfile {
+    // Unique per app; compared case-insensitively
+    pub name: String,
+    // Appended to the arguments the entry already carries
+    #[serde(default)]
+    pub arguments: Option<String>,
+    // Replaces the entry's working directory
+    #[serde(default)]
+    pub working_dir: Option<String>,
+    // Launch elevated (the "runas" verb on Windows)
+    #[serde(default)]
+    pub run_as_admin: bool,
+    // None keeps the shortcut's own show command
+    #[serde(default)]
+    pub window_state: Option<WindowState>,
+    // Variables set for the launched process; %VAR% references are expanded
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WindowState {
+    Normal,
+    Minimized,
+    Maximized,
+}
+
+impl WindowState {
+    // The matching shell link ShowCommand (SW_SHOWNORMAL, SW_SHOWMAXIMIZED, SW_SHOWMINNOACTIVE)
+    pub fn show_command(self) -> u32 {
+        match self {
+            WindowState::Normal => 1,
+            WindowState::Maximized => 3,
+            WindowState::Minimized => 7,
+        }
+    }
+}
+
+// A user-added folder to scan, with its own filtering rules. Glob patterns are matched
+// case-insensitively against paths relative to the root, using '/' as separator.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScanRoot {
+    pub path: String,
+    // Subdirectory levels to descend into; None scans the whole tree, 0 only the root itself
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+    #[serde(default = "default_scan_extensions")]
+    pub extensions: Vec<String>,
+    // When non-empty, only files matching one of these are picked up
+    #[serde(default)]
+    pub include: Vec<String>,
+    // Files and directories matching any of these are skipped
+    #[serde(default = "default_scan_excludes")]
+    pub exclude: Vec<String>,
+}
+
+pub fn default_scan_extensions() -> Vec<String> {
+    ["lnk", "url", "exe"].iter().map(|ext| ext.to_string()).collect()
+}
+
+pub fn default_cache_ttl_secs() -> u64 {
+    3600
+}
+
+pub fn default_icon_cache_max_bytes() -> u64 {
+    64 * 1024 * 1024
+}
+
+pub fn default_scan_excludes() -> Vec<String> {
+    crate::discovery::SKIP_DIRS.iter().map(|dir| format!("**/{}", dir)).collect()
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            custom_icons: HashMap::new(),
+            moved_apps: HashMap::new(),
+            pinned_apps: Vec::new(),
+            recent_apps: Vec::new(),
+            is_grid_view: true,
+            categories: HashMap::new(),
+            disabled_sources: Vec::new(),
+            scan_roots: Vec::new(),
+            cache_ttl_secs: default_cache_ttl_secs(),
+            icon_cache_max_bytes: default_icon_cache_max_bytes(),
+            hide_broken_apps: false,
+            launch_profiles: HashMap::new(),
+        }
+    }
+}
+
+impl AppSettings {
+    pub fn launch_profile(&self, app_path: &str, name: &str) -> Option<&LaunchProfile> {
+        self.launch_profiles.get(app_path)?
+            .iter()
+            .find(|profile| profile.name.eq_ignore_ascii_case(name))
+    }
+}
+
+fn settings_path() -> Result<PathBuf, String> {
+    let app_dir = tauri::api::path::app_data_dir(&tauri::Config::default())
+        .ok_or_else(|| "Failed to get app directory".to_string())?;
+    Ok(app_dir.join("settings.json"))
+}
+
+pub fn load() -> Result<AppSettings, String> {
+    let settings_file = settings_path()?;
+    log_error(&format!("Loading settings from: {}", settings_file.display()));
+
+    if !settings_file.exists() {
+        log_error("Settings file does not exist, creating default settings");
+        return Ok(AppSettings::default());
+    }
+
+    let content = fs::read_to_string(&settings_file)
+        .map_err(|e| {
+            log_error(&format!("Failed to read settings: {}", e));
+            format!("Failed to read settings: {}", e)
+        })?;
+
+    serde_json::from_str(&content)
+        .map_err(|e| {
+            log_error(&format!("Failed to parse settings: {}", e));
+            format!("Failed to parse settings: {}", e)
+        })
+}
+
+// Settings for background work that can't surface an error; falls back to defaults
+pub fn load_or_default() -> AppSettings {
+    load().unwrap_or_default()
+}
+
+pub fn save(settings: &AppSettings) -> Result<(), String> {
+    let settings_file = settings_path()?;
+    let app_dir = settings_file.parent()
+        .ok_or_else(|| "Failed to get app directory".to_string())?;
+
+    log_error(&format!("Saving settings to: {}", app_dir.display()));
+
+    fs::create_dir_all(app_dir)
+        .map_err(|e| {
+            log_error(&format!("Failed to create app directory: {}", e));
+            format!("Failed to create app directory: {}", e)
+        })?;
+
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| {
+            log_error(&format!("Failed to serialize settings: {}", e));
+            format!("Failed to serialize settings: {}", e)
+        })?;
+
+    fs::write(&settings_file, &content)
+        .map_err(|e| {
+            log_error(&format!("Failed to write settings: {}", e));
+            format!("Failed to write settings: {}", e)
+        })?;
+
+    log_error(&format!("Successfully saved settings: {}", content));
+    Ok(())
+}
@@ -0,0 +1,246 @@
+// Icons as the Windows shell draws them, read from the system image lists. Used for
+// files whose icon can't be decoded directly, such as documents and shell items.
+
+use std::path::Path;
+use image::RgbaImage;
+use windows::core::PCWSTR;
+use windows::Win32::Graphics::Gdi::{
+    DeleteObject, GetDC, GetDIBits, GetObjectW, ReleaseDC,
+    BITMAP, BITMAPINFO, BITMAPINFOHEADER, BI_RGB, DIB_RGB_COLORS, HBITMAP
+};
+use windows::Win32::Storage::FileSystem::FILE_FLAGS_AND_ATTRIBUTES;
+use windows::Win32::UI::Controls::{IImageList, ILD_TRANSPARENT};
+use windows::Win32::UI::Shell::{
+    SHFILEINFOW, SHGFI_SYSICONINDEX, SHGetFileInfoW, SHGetImageList,
+    SHIL_EXTRALARGE, SHIL_JUMBO, SHIL_LARGE, SHIL_SMALL
+};
+use windows::Win32::UI::WindowsAndMessaging::{DestroyIcon, GetIconInfo, HICON, ICONINFO};
+use crate::icons::encode_png;
+use crate::internet_shortcut;
+use crate::lnk;
+use crate::log_error;
+
+// Shell image lists from smallest to largest, with the size of their images
+const IMAGE_LISTS: &[(u32, u32)] = &[
+    (SHIL_SMALL, 16),
+    (SHIL_LARGE, 32),
+    (SHIL_EXTRALARGE, 48),
+    (SHIL_JUMBO, 256),
+];
+// Size of the image the jumbo list pads out when an icon has no 256 px version
+const JUMBO_PADDED_EXTENT: u32 = 48;
+
+// Render the icon the shell shows for `path` once per entry of `sizes`. Each size is
+// drawn from the smallest native image at least that large (16, 32, 48 or 256 px),
+// so small sizes keep their hand-tuned pixels and large ones aren't upscaled.
+pub fn extract_icon_pngs(path: &str, sizes: &[u32]) -> Result<Vec<Vec<u8>>, String> {
+    let Some(icon_index) = system_icon_index(path) else {
+        return match fallback_icon_path(path) {
+            Some(fallback) => extract_icon_pngs(&fallback, sizes),
+            None => Err("Failed to get icon".into()),
+        };
+    };
+
+    let mut natives = NativeImages::new(icon_index);
+    sizes.iter()
+        .map(|&size| {
+            let image = natives.best_for(size)?;
+            encode_png(image, size)
+        })
+        .collect()
+}
+
+// Index of the icon for `path` in the system image lists
+fn system_icon_index(path: &str) -> Option<i32> {
+    unsafe {
+        let path_wide: Vec<u16> = path.encode_utf16().chain(std::iter::once(0)).collect();
+        let mut file_info: SHFILEINFOW = std::mem::zeroed();
+
+        let result = SHGetFileInfoW(
+            PCWSTR(path_wide.as_ptr()),
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            Some(&mut file_info),
+            std::mem::size_of::<SHFILEINFOW>() as u32,
+            SHGFI_SYSICONINDEX
+        );
+
+        (result != 0).then_some(file_info.iIcon)
+    }
+}
+
+// File to take the icon from when the shell has none for `path`
+fn fallback_icon_path(path: &str) -> Option<String> {
+    let path_lower = path.to_lowercase();
+
+    if path_lower.ends_with(".lnk") {
+        // For .lnk files, get icon from target
+        return lnk::resolve_shortcut(Path::new(path)).ok();
+    }
+
+    if path_lower.ends_with(".url") {
+        // For .url files, use the icon of the default browser
+        return internet_shortcut::default_browser_icon().map(|(browser, _)| browser);
+    }
+
+    None
+}
+
+// Images of one system icon, loaded from the shell image lists on first use
+struct NativeImages {
+    icon_index: i32,
+    loaded: Vec<(u32, Option<RgbaImage>)>,
+}
+
+impl NativeImages {
+    fn new(icon_index: i32) -> Self {
+        Self { icon_index, loaded: Vec::new() }
+    }
+
+    // Smallest native image at least `size` px, else the largest available one
+    fn best_for(&mut self, size: u32) -> Result<&RgbaImage, String> {
+        let larger = IMAGE_LISTS.iter().filter(|(_, native)| *native >= size);
+        let smaller = IMAGE_LISTS.iter().rev().filter(|(_, native)| *native < size);
+
+        let list = larger.chain(smaller)
+            .map(|&(list, _)| list)
+            .find(|&list| self.load(list).is_some())
+            .ok_or_else(|| "Failed to get icon".to_string())?;
+
+        Ok(self.load(list).unwrap())
+    }
+
+    fn load(&mut self, list: u32) -> Option<&RgbaImage> {
+        let position = match self.loaded.iter().position(|(loaded, _)| *loaded == list) {
+            Some(position) => position,
+            None => {
+                let image = image_list_icon(list, self.icon_index)
+                    .map_err(|e| log_error(&format!("Failed to read image list {}: {}", list, e)))
+                    .ok()
+                    // Icons without a 256 px image come back from the jumbo list as
+                    // their 48 px image in the corner of an empty canvas
+                    .filter(|image| list != SHIL_JUMBO || !fits_in_corner(image, JUMBO_PADDED_EXTENT));
+                self.loaded.push((list, image));
+                self.loaded.len() - 1
+            }
+        };
+        self.loaded[position].1.as_ref()
+    }
+}
+
+fn image_list_icon(list: u32, icon_index: i32) -> Result<RgbaImage, String> {
+    unsafe {
+        let image_list: IImageList = SHGetImageList(list as i32).map_err(|e| e.to_string())?;
+        let hicon = image_list.GetIcon(icon_index, ILD_TRANSPARENT.0).map_err(|e| e.to_string())?;
+
+        let image = icon_to_rgba(hicon);
+
+        if let Err(e) = DestroyIcon(hicon) {
+            log_error(&format!("Failed to destroy icon: {:?}", e));
+        }
+
+        image
+    }
+}
+
+// Read an icon's pixels with their real alpha. 32-bit icons carry it in the color
+// bitmap; older ones only have the AND mask, where set bits are transparent.
+fn icon_to_rgba(hicon: HICON) -> Result<RgbaImage, String> {
+    unsafe {
+        let mut info = ICONINFO::default();
+        GetIconInfo(hicon, &mut info).map_err(|e| format!("Failed to read icon: {}", e))?;
+
+        let image = if info.hbmColor.is_invalid() {
+            // Monochrome icons stack the AND mask on top of the XOR image
+            bitmap_bits(info.hbmMask).and_then(|(width, height, bits)| {
+                let half = (width * height / 2 * 4) as usize;
+                let (mask, mut color) = (bits[..half].to_vec(), bits[half..].to_vec());
+                apply_mask(&mut color, &mask);
+                bgra_to_image(width, height / 2, color)
+            })
+        } else {
+            bitmap_bits(info.hbmColor).and_then(|(width, height, mut color)| {
+                if !color.chunks_exact(4).any(|pixel| pixel[3] != 0) {
+                    let (_, _, mask) = bitmap_bits(info.hbmMask)?;
+                    apply_mask(&mut color, &mask);
+                }
+                bgra_to_image(width, height, color)
+            })
+        };
+
+        // GetIconInfo hands out copies of both bitmaps
+        if !info.hbmColor.is_invalid() {
+            DeleteObject(info.hbmColor);
+        }
+        DeleteObject(info.hbmMask);
+
+        image.ok_or_else(|| "Failed to read icon bitmap".to_string())
+    }
+}
+
+// Pixels of a bitmap as top-down 32-bit BGRA, whatever its own format
+fn bitmap_bits(hbitmap: HBITMAP) -> Option<(u32, u32, Vec<u8>)> {
+    unsafe {
+        let mut bitmap = BITMAP::default();
+        let read = GetObjectW(
+            hbitmap,
+            std::mem::size_of::<BITMAP>() as i32,
+            Some(&mut bitmap as *mut BITMAP as *mut _)
+        );
+        if read == 0 || bitmap.bmWidth <= 0 || bitmap.bmHeight == 0 {
+            return None;
+        }
+
+        let (width, height) = (bitmap.bmWidth, bitmap.bmHeight.abs());
+        let mut info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
+                biWidth: width,
+                biHeight: -height,  // Negative for top-down rows
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let mut bits = vec![0u8; (width * height * 4) as usize];
+        let hdc = GetDC(None);
+        let lines = GetDIBits(
+            hdc,
+            hbitmap,
+            0,
+            height as u32,
+            Some(bits.as_mut_ptr() as *mut _),
+            &mut info,
+            DIB_RGB_COLORS
+        );
+        ReleaseDC(None, hdc);
+
+        (lines == height).then_some((width as u32, height as u32, bits))
+    }
+}
+
+// Make pixels transparent where the AND mask (expanded to 32 bits) is set and opaque
+// elsewhere
+fn apply_mask(bgra: &mut [u8], mask: &[u8]) {
+    for (pixel, mask) in bgra.chunks_exact_mut(4).zip(mask.chunks_exact(4)) {
+        pixel[3] = if mask[..3].iter().any(|&channel| channel != 0) { 0 } else { 255 };
+    }
+}
+
+fn bgra_to_image(width: u32, height: u32, mut bits: Vec<u8>) -> Option<RgbaImage> {
+    for pixel in bits.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+    RgbaImage::from_raw(width, height, bits)
+}
+
+// Whether every visible pixel lies within the top-left `extent` x `extent` square of a
+// larger image
+fn fits_in_corner(image: &RgbaImage, extent: u32) -> bool {
+    image.width() > extent
+        && image.enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel[3] != 0)
+            .all(|(x, y, _)| x < extent && y < extent)
+}
@@ -0,0 +1,336 @@
+// Discovery providers. Each source knows how to enumerate its apps, which paths to
+// watch for changes, where an entry's icon comes from and how to launch it. New
+// sources only need an AppSource impl and an entry in `registered_sources`.
+
+use std::path::{Path, PathBuf};
+use crate::discovery::{self, AppInfo, ScanContext, ScanRules};
+use crate::launcher::{LaunchError, LaunchRequest, Launcher};
+use crate::log_error;
+use crate::settings::{AppSettings, LaunchProfile};
+
+pub trait AppSource: Send + Sync {
+    // Stable identifier stored in AppInfo::source and AppSettings::disabled_sources
+    fn id(&self) -> &'static str;
+    // Long walks should go through discovery::scan_directories, which honours the
+    // context's cancellation and reports progress
+    fn enumerate(&self, settings: &AppSettings, ctx: &ScanContext) -> Vec<AppInfo>;
+    fn watch_paths(&self, settings: &AppSettings) -> Vec<PathBuf>;
+    // Whether a file below the watch paths would be enumerated by this source; lets
+    // the file watcher re-parse single files instead of rescanning
+    fn accepts(&self, path: &Path, settings: &AppSettings) -> bool;
+    // File the entry's icon should be extracted from
+    fn resolve_icon(&self, app: &AppInfo) -> Option<String>;
+    // Start the entry through `launcher`, the platform's SystemLauncher outside tests
+    fn launch(&self, app: &AppInfo, launcher: &dyn Launcher) -> Result<(), LaunchError>;
+    fn launch_with_profile(&self, app: &AppInfo, profile: &LaunchProfile, launcher: &dyn Launcher) -> Result<(), LaunchError> {
+        launcher.open(&LaunchRequest::with_profile(app, profile))
+    }
+}
+
+pub fn registered_sources() -> Vec<Box<dyn AppSource>> {
+    vec![
+        Box::new(StartMenuSource),
+        Box::new(InternetShortcutSource),
+        Box::new(ExtraDirectorySource),
+        #[cfg(target_os = "linux")]
+        Box::new(DesktopEntrySource),
+    ]
+}
+
+pub fn enabled_sources(settings: &AppSettings) -> Vec<Box<dyn AppSource>> {
+    registered_sources()
+        .into_iter()
+        .filter(|source| !settings.disabled_sources.iter().any(|id| id == source.id()))
+        .collect()
+}
+
+pub fn source_for(app: &AppInfo) -> Option<Box<dyn AppSource>> {
+    registered_sources()
+        .into_iter()
+        .find(|source| source.id() == app.source)
+}
+
+// Scan directories for the given extensions and tag the results with the source id
+fn scan_tagged(dirs: &[PathBuf], extensions: &[&str], source: &str, ctx: &ScanContext) -> Vec<AppInfo> {
+    let rules = ScanRules::for_extensions(extensions);
+    let roots: Vec<_> = dirs.iter().map(|dir| (dir.clone(), &rules)).collect();
+    let mut apps = discovery::scan_directories(&roots, ctx);
+    tag(&mut apps, source);
+    apps
+}
+
+fn accepts_tagged(dirs: &[PathBuf], extensions: &[&str], path: &Path) -> bool {
+    let rules = ScanRules::for_extensions(extensions);
+    dirs.iter().any(|dir| rules.matches(dir, path))
+}
+
+fn tag(apps: &mut [AppInfo], source: &str) {
+    for app in apps {
+        app.source = source.to_string();
+    }
+}
+
+// .lnk shortcuts in the all-users and per-user Start Menu
+pub struct StartMenuSource;
+
+impl AppSource for StartMenuSource {
+    fn id(&self) -> &'static str {
+        "start_menu"
+    }
+
+    fn enumerate(&self, settings: &AppSettings, ctx: &ScanContext) -> Vec<AppInfo> {
+        scan_tagged(&self.watch_paths(settings), &["lnk"], self.id(), ctx)
+    }
+
+    fn watch_paths(&self, _settings: &AppSettings) -> Vec<PathBuf> {
+        discovery::start_menu_paths()
+    }
+
+    fn accepts(&self, path: &Path, settings: &AppSettings) -> bool {
+        accepts_tagged(&self.watch_paths(settings), &["lnk"], path)
+    }
+
+    fn resolve_icon(&self, app: &AppInfo) -> Option<String> {
+        // The shell applies the shortcut's own icon location
+        Some(app.path.clone())
+    }
+
+    fn launch(&self, app: &AppInfo, launcher: &dyn Launcher) -> Result<(), LaunchError> {
+        // The shell applies the shortcut's stored arguments and working directory
+        launcher.open(&LaunchRequest::new(&app.path, None, None, None))
+    }
+}
+
+// .url internet shortcuts from the Start Menu and the user's Favorites
+pub struct InternetShortcutSource;
+
+impl AppSource for InternetShortcutSource {
+    fn id(&self) -> &'static str {
+        "internet_shortcuts"
+    }
+
+    fn enumerate(&self, settings: &AppSettings, ctx: &ScanContext) -> Vec<AppInfo> {
+        scan_tagged(&self.watch_paths(settings), &["url"], self.id(), ctx)
+    }
+
+    fn watch_paths(&self, _settings: &AppSettings) -> Vec<PathBuf> {
+        let mut paths = discovery::start_menu_paths();
+        if let Ok(profile) = std::env::var("USERPROFILE") {
+            paths.push(PathBuf::from(profile).join("Favorites"));
+        }
+        paths
+    }
+
+    fn accepts(&self, path: &Path, settings: &AppSettings) -> bool {
+        accepts_tagged(&self.watch_paths(settings), &["url"], path)
+    }
+
+    fn resolve_icon(&self, app: &AppInfo) -> Option<String> {
+        Some(app.path.clone())
+    }
+
+    fn launch(&self, app: &AppInfo, launcher: &dyn Launcher) -> Result<(), LaunchError> {
+        launcher.open(&LaunchRequest::new(&app.path, None, None, None))
+    }
+}
+
+// Shortcuts and executables in the scan roots the user added in settings
+pub struct ExtraDirectorySource;
+
+impl AppSource for ExtraDirectorySource {
+    fn id(&self) -> &'static str {
+        "extra_directories"
+    }
+
+    fn enumerate(&self, settings: &AppSettings, ctx: &ScanContext) -> Vec<AppInfo> {
+        let mut compiled = Vec::new();
+        for root in &settings.scan_roots {
+            match ScanRules::from_root(root) {
+                Ok(rules) => compiled.push((PathBuf::from(&root.path), rules)),
+                Err(e) => log_error(&format!("Skipping scan root {}: {}", root.path, e)),
+            }
+        }
+
+        let roots: Vec<_> = compiled.iter().map(|(dir, rules)| (dir.clone(), rules)).collect();
+        let mut apps = discovery::scan_directories(&roots, ctx);
+        tag(&mut apps, self.id());
+        apps
+    }
+
+    fn watch_paths(&self, settings: &AppSettings) -> Vec<PathBuf> {
+        settings.scan_roots.iter().map(|root| PathBuf::from(&root.path)).collect()
+    }
+
+    fn accepts(&self, path: &Path, settings: &AppSettings) -> bool {
+        settings.scan_roots.iter().any(|root| {
+            ScanRules::from_root(root).is_ok_and(|rules| rules.matches(Path::new(&root.path), path))
+        })
+    }
+
+    fn resolve_icon(&self, app: &AppInfo) -> Option<String> {
+        Some(app.path.clone())
+    }
+
+    fn launch(&self, app: &AppInfo, launcher: &dyn Launcher) -> Result<(), LaunchError> {
+        let lower = app.path.to_lowercase();
+        if lower.ends_with(".lnk") || lower.ends_with(".url") {
+            return launcher.open(&LaunchRequest::new(&app.path, None, None, None));
+        }
+        launcher.open(&LaunchRequest::new(
+            &app.path,
+            app.arguments.as_deref(),
+            app.working_dir.as_deref(),
+            app.show_command,
+        ))
+    }
+}
+
+// freedesktop .desktop entries from the XDG data directories
+#[cfg(target_os = "linux")]
+pub struct DesktopEntrySource;
+
+#[cfg(target_os = "linux")]
+impl AppSource for DesktopEntrySource {
+    fn id(&self) -> &'static str {
+        "desktop_entries"
+    }
+
+    fn enumerate(&self, _settings: &AppSettings, _ctx: &ScanContext) -> Vec<AppInfo> {
+        let mut apps = crate::desktop_entry::scan_applications();
+        tag(&mut apps, self.id());
+        apps
+    }
+
+    fn watch_paths(&self, _settings: &AppSettings) -> Vec<PathBuf> {
+        crate::desktop_entry::application_dirs()
+    }
+
+    fn accepts(&self, path: &Path, settings: &AppSettings) -> bool {
+        path.extension().is_some_and(|ext| ext == "desktop")
+            && self.watch_paths(settings).iter().any(|dir| path.starts_with(dir))
+    }
+
+    fn resolve_icon(&self, app: &AppInfo) -> Option<String> {
+        // The Icon key is usually a theme icon name rather than a file
+        let icon = app.icon_path.as_deref()?;
+        crate::icon_theme::find_icon(icon, crate::icons::DEFAULT_ICON_SIZE, 1)
+            .map(|path| path.to_string_lossy().into_owned())
+    }
+
+    fn launch(&self, app: &AppInfo, launcher: &dyn Launcher) -> Result<(), LaunchError> {
+        launcher.open(&exec_request(app, None)?)
+    }
+
+    fn launch_with_profile(&self, app: &AppInfo, profile: &LaunchProfile, launcher: &dyn Launcher) -> Result<(), LaunchError> {
+        launcher.open(&exec_request(app, Some(profile))?)
+    }
+}
+
+// The entry's Exec command, with a profile's extra arguments and overrides
+#[cfg(target_os = "linux")]
+fn exec_request(app: &AppInfo, profile: Option<&LaunchProfile>) -> Result<LaunchRequest, LaunchError> {
+    use crate::desktop_entry::{quote_arg, DesktopEntry};
+
+    let entry = DesktopEntry::open(Path::new(&app.path))?;
+    let argv = entry.expand_exec(&[]);
+    let (program, args) = argv.split_first()
+        .ok_or_else(|| LaunchError::Other { message: format!("No Exec command in {}", app.path) })?;
+    let arguments = args.iter().map(|arg| quote_arg(arg)).collect::<Vec<_>>().join(" ");
+
+    // The entry as the program it runs, so profiles apply as they do to executables
+    let command = AppInfo {
+        path: program.clone(),
+        target: Some(program.clone()),
+        arguments: Some(arguments),
+        working_dir: entry.working_dir.clone(),
+        show_command: None,
+        ..app.clone()
+    };
+    Ok(match profile {
+        Some(profile) => LaunchRequest::with_profile(&command, profile),
+        None => LaunchRequest::new(&command.path, command.arguments.as_deref(), command.working_dir.as_deref(), None),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::launcher::RecordingLauncher;
+    use crate::settings::WindowState;
+
+    fn app(path: &str) -> AppInfo {
+        AppInfo {
+            path: path.into(),
+            target: Some("C:\\Tools\\tool.exe".into()),
+            arguments: Some("--stored".into()),
+            working_dir: Some("C:\\Tools".into()),
+            show_command: Some(3),
+            ..Default::default()
+        }
+    }
+
+    // The single request `launch` handed to the launcher
+    fn launched(launch: impl FnOnce(&dyn Launcher) -> Result<(), LaunchError>) -> LaunchRequest {
+        let launcher = RecordingLauncher::default();
+        launch(&launcher).unwrap();
+        let mut opened = launcher.opened.into_inner().unwrap();
+        assert_eq!(opened.len(), 1);
+        opened.remove(0)
+    }
+
+    #[test]
+    fn shortcuts_are_opened_as_they_are() {
+        // The shell applies what the shortcut stores, so nothing is passed along
+        for path in ["C:\\Menu\\Tool.lnk", "C:\\Menu\\Site.url"] {
+            let expected = LaunchRequest::new(path, None, None, None);
+            assert_eq!(launched(|launcher| StartMenuSource.launch(&app(path), launcher)), expected);
+            assert_eq!(launched(|launcher| InternetShortcutSource.launch(&app(path), launcher)), expected);
+            assert_eq!(launched(|launcher| ExtraDirectorySource.launch(&app(path), launcher)), expected);
+        }
+    }
+
+    #[test]
+    fn executables_carry_their_launch_details() {
+        let exe = app("C:\\Tools\\tool.exe");
+        assert_eq!(
+            launched(|launcher| ExtraDirectorySource.launch(&exe, launcher)),
+            LaunchRequest::new(&exe.path, Some("--stored"), Some("C:\\Tools"), Some(3)),
+        );
+    }
+
+    #[test]
+    fn profiles_apply_on_top_of_the_entry() {
+        let profile = LaunchProfile {
+            name: "Admin".into(),
+            arguments: Some("--verbose".into()),
+            run_as_admin: true,
+            window_state: Some(WindowState::Minimized),
+            ..Default::default()
+        };
+        let exe = app("C:\\Tools\\tool.exe");
+        let request = launched(|launcher| ExtraDirectorySource.launch_with_profile(&exe, &profile, launcher));
+
+        assert_eq!(request.file, exe.path);
+        assert_eq!(request.arguments.as_deref(), Some("--stored --verbose"));
+        assert_eq!(request.working_dir.as_deref(), Some("C:\\Tools"));
+        assert_eq!(request.show_command, Some(WindowState::Minimized.show_command()));
+        assert!(request.run_as_admin);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn desktop_entries_run_their_exec_command() {
+        let dir = std::env::temp_dir().join(format!("axon-sources-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("editor.desktop");
+        std::fs::write(&path, "[Desktop Entry]\nType=Application\nName=Editor\n\
+            Exec=/usr/bin/editor --new-window \"My Notes\" %F\nPath=/home/user\n").unwrap();
+        let entry = AppInfo { path: path.to_string_lossy().into_owned(), ..Default::default() };
+
+        assert_eq!(
+            launched(|launcher| DesktopEntrySource.launch(&entry, launcher)),
+            LaunchRequest::new("/usr/bin/editor", Some("--new-window \"My Notes\""), Some("/home/user"), None),
+        );
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,269 @@
+// Launch history kept under <app data>/usage.json: how often and how recently each
+// app was started, and from where. Ranks apps by frecency (frequency weighted by
+// recency) so "most used" ordering doesn't depend on the frontend's storage.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+use serde::{Deserialize, Serialize};
+use crate::app_index;
+use crate::discovery::AppInfo;
+use crate::log_error;
+
+const USAGE_VERSION: u64 = 1;
+const USAGE_FILE: &str = "usage.json";
+// Launch times kept per app for the recency part of the score
+const MAX_SAMPLES: usize = 10;
+
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+// Weight of a launch by its age, as in Firefox's frecency
+const RECENCY_BUCKETS: &[(u64, f64)] = &[
+    (4 * DAY_MILLIS, 100.0),
+    (14 * DAY_MILLIS, 70.0),
+    (31 * DAY_MILLIS, 50.0),
+    (90 * DAY_MILLIS, 30.0),
+];
+const OLD_LAUNCH_WEIGHT: f64 = 10.0;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct AppUsage {
+    pub launch_count: u64,
+    pub last_launched: u64,  // Milliseconds since the Unix epoch
+    // Entry point of the last launch, e.g. "app_list" or "profile:<name>"
+    pub last_source: String,
+    // Most recent launch times, newest first
+    #[serde(default)]
+    pub recent: Vec<u64>,
+}
+
+impl AppUsage {
+    // Launch count times the average weight of the recent launches
+    fn frecency(&self, now: u64) -> f64 {
+        if self.recent.is_empty() {
+            return 0.0;
+        }
+        let weights: f64 = self.recent.iter()
+            .map(|&launched| {
+                let age = now.saturating_sub(launched);
+                RECENCY_BUCKETS.iter()
+                    .find(|(max_age, _)| age <= *max_age)
+                    .map(|(_, weight)| *weight)
+                    .unwrap_or(OLD_LAUNCH_WEIGHT)
+            })
+            .sum();
+        self.launch_count as f64 * weights / self.recent.len() as f64
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+struct UsageFile {
+    version: u64,
+    apps: HashMap<String, AppUsage>,
+}
+
+// Payload of get_ranked_apps: the entry with its usage
+#[derive(Serialize, Debug, Clone)]
+pub struct RankedApp {
+    #[serde(flatten)]
+    pub app: AppInfo,
+    pub score: f64,
+    pub launch_count: u64,
+    pub last_launched: Option<u64>,
+}
+
+// Registered as Tauri managed state
+pub struct UsageStore {
+    path: PathBuf,
+    // Loaded on first use and written after every launch
+    apps: Mutex<Option<HashMap<String, AppUsage>>>,
+    // Set when an unreadable usage.json couldn't be moved aside; launches are then
+    // only counted in memory rather than written over it
+    read_error: Mutex<Option<String>>,
+}
+
+impl Default for UsageStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UsageStore {
+    pub fn new() -> Self {
+        let dir = tauri::api::path::app_data_dir(&tauri::Config::default())
+            .unwrap_or_else(|| PathBuf::from("."));
+        Self::in_dir(dir)
+    }
+
+    fn in_dir(dir: PathBuf) -> Self {
+        Self { path: dir.join(USAGE_FILE), apps: Mutex::new(None), read_error: Mutex::new(None) }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<HashMap<String, AppUsage>>> {
+        let mut apps = self.apps.lock().unwrap();
+        if apps.is_none() {
+            *apps = Some(self.read());
+        }
+        apps
+    }
+
+    fn read(&self) -> HashMap<String, AppUsage> {
+        let Ok(contents) = fs::read_to_string(&self.path) else { return HashMap::new() };
+
+        let problem = match serde_json::from_str::<UsageFile>(&contents) {
+            Ok(file) if file.version == USAGE_VERSION => return file.apps,
+            Ok(file) => format!("Usage data has unsupported version {}", file.version),
+            Err(e) => format!("Usage data is corrupt: {}", e),
+        };
+
+        // Keep the file, which may come from a newer version of the app, rather than
+        // overwriting it with the next launch
+        let backup = self.path.with_extension("json.bad");
+        match fs::rename(&self.path, &backup) {
+            Ok(()) => log_error(&format!("{}; moved it to {}", problem, backup.display())),
+            Err(e) => {
+                let message = format!("{}; failed to move it aside: {}", problem, e);
+                log_error(&message);
+                *self.read_error.lock().unwrap() = Some(message);
+            }
+        }
+        HashMap::new()
+    }
+
+    fn write(&self, apps: &HashMap<String, AppUsage>) -> Result<(), String> {
+        let file = UsageFile { version: USAGE_VERSION, apps: apps.clone() };
+        let contents = serde_json::to_vec(&file)
+            .map_err(|e| format!("Failed to serialize usage data: {}", e))?;
+
+        if let Some(dir) = self.path.parent() {
+            let _ = fs::create_dir_all(dir);
+        }
+        app_index::write_atomic(&self.path, &contents)
+            .map_err(|e| format!("Failed to write usage data {}: {}", self.path.display(), e))
+    }
+
+    // Count a launch of `path` from `source`
+    pub fn record(&self, path: &str, source: &str) -> Result<(), String> {
+        let now = now_millis();
+        let mut guard = self.lock();
+        let apps = guard.get_or_insert_with(HashMap::new);
+
+        let usage = apps.entry(path.to_string()).or_default();
+        usage.launch_count += 1;
+        usage.last_launched = now;
+        usage.last_source = source.to_string();
+        usage.recent.insert(0, now);
+        usage.recent.truncate(MAX_SAMPLES);
+
+        if let Some(e) = self.read_error.lock().unwrap().clone() {
+            return Err(e);
+        }
+        self.write(apps)
+    }
+
+    // `apps` whose name contains `query` (all of them for an empty query), most
+    // frecent first; unused apps follow, those whose name starts with the query first
+    pub fn rank(&self, apps: Vec<AppInfo>, query: &str) -> Vec<RankedApp> {
+        let now = now_millis();
+        let query = query.trim().to_lowercase();
+        let guard = self.lock();
+        let recorded = guard.as_ref();
+
+        let mut ranked: Vec<(bool, RankedApp)> = apps.into_iter()
+            .filter_map(|app| {
+                let name = app.name.to_lowercase();
+                if !name.contains(&query) {
+                    return None;
+                }
+                let prefix = name.starts_with(&query);
+                // Launches are recorded under the entry's path; older ones may name
+                // a shortcut that has since been merged into it
+                let usage = std::iter::once(&app.path)
+                    .chain(&app.shortcuts)
+                    .filter_map(|path| recorded?.get(path))
+                    .max_by_key(|usage| usage.last_launched);
+
+                Some((prefix, RankedApp {
+                    score: usage.map(|usage| usage.frecency(now)).unwrap_or(0.0),
+                    launch_count: usage.map(|usage| usage.launch_count).unwrap_or(0),
+                    last_launched: usage.map(|usage| usage.last_launched),
+                    app,
+                }))
+            })
+            .collect();
+
+        ranked.sort_by(|(a_prefix, a), (b_prefix, b)| {
+            b.score.total_cmp(&a.score)
+                .then(b_prefix.cmp(a_prefix))
+                .then_with(|| a.app.name.to_lowercase().cmp(&b.app.name.to_lowercase()))
+        });
+        ranked.into_iter().map(|(_, app)| app).collect()
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|since| since.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("axon-usage-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn app(name: &str) -> AppInfo {
+        AppInfo { name: name.into(), path: format!("/apps/{}", name), ..Default::default() }
+    }
+
+    #[test]
+    fn ranks_by_frecency_then_prefix_then_name() {
+        let dir = temp_dir("rank");
+        let store = UsageStore::in_dir(dir.clone());
+        store.record("/apps/Zed", "app_list").unwrap();
+        store.record("/apps/Zed", "search").unwrap();
+        store.record("/apps/Vim", "app_list").unwrap();
+
+        let ranked = store.rank(vec![app("Beta"), app("Vim"), app("Alpha"), app("Zed")], "");
+        let names: Vec<_> = ranked.iter().map(|ranked| ranked.app.name.as_str()).collect();
+        assert_eq!(names, ["Zed", "Vim", "Alpha", "Beta"]);
+        assert_eq!(ranked[0].launch_count, 2);
+        assert_eq!(ranked[2].last_launched, None);
+
+        let ranked = store.rank(vec![app("Gamma"), app("Paralpha"), app("Alpha")], "ALP");
+        let names: Vec<_> = ranked.iter().map(|ranked| ranked.app.name.as_str()).collect();
+        assert_eq!(names, ["Alpha", "Paralpha"]);
+
+        // Launches survive a restart
+        let reopened = UsageStore::in_dir(dir.clone());
+        assert_eq!(reopened.rank(vec![app("Zed")], "")[0].launch_count, 2);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unreadable_usage_data_is_moved_aside() {
+        for (name, contents) in [
+            ("corrupt", "{\"version\": 1, \"apps\": ".to_string()),
+            ("newer", format!("{{\"version\": {}, \"apps\": {{}}, \"extra\": true}}", USAGE_VERSION + 1)),
+        ] {
+            let dir = temp_dir(name);
+            fs::write(dir.join(USAGE_FILE), &contents).unwrap();
+
+            let store = UsageStore::in_dir(dir.clone());
+            store.record("/apps/Zed", "app_list").unwrap();
+
+            assert_eq!(fs::read_to_string(dir.join("usage.json.bad")).unwrap(), contents);
+            let reopened = UsageStore::in_dir(dir.clone());
+            assert_eq!(reopened.rank(vec![app("Zed")], "")[0].launch_count, 1);
+            let _ = fs::remove_dir_all(&dir);
+        }
+    }
+}
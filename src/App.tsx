@@ -1,4 +1,5 @@
 import React, { useState, useEffect, useRef } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import { SearchBar } from './components/SearchBar';
 import { AppList } from './components/AppList';
 import { Settings } from './components/Settings';
@@ -10,10 +11,11 @@ import { useSettingsStore } from './store/useSettingsStore';
 import { Titlebar } from './components/Titlebar';
 import { LoadingScreen } from './components/LoadingScreen';
 import { useDelayedLoading } from './hooks/useDelayedLoading';
+import { AppChanges } from './types/app';
 
 function App() {
   const [selectedCategory, setSelectedCategory] = useState<string | null>(null);
-  const { loadApps, isLoading } = useAppStore();
+  const { loadApps, applyAppChanges, isLoading } = useAppStore();
   const { initializeSettings } = useSettingsStore();
   const scrollContainerRef = useRef<HTMLDivElement>(null);
   // Show loading screen for at least 1.2 seconds for better UX
@@ -30,6 +32,17 @@ function App() {
     initializeSettings();
   }, []);
 
+  // Apply shortcut changes picked up by the backend file watcher
+  useEffect(() => {
+    const unlisten = listen<AppChanges>('apps://changed', (event) => {
+      applyAppChanges(event.payload);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   return (
     <ThemeProvider>
       <div
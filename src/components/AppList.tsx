@@ -1,6 +1,6 @@
 import React, { useState } from 'react';
 import { useAppStore } from '../store/useAppStore';
-import { AppInfo, AppCategory } from '../types/app';
+import { AppInfo, AppCategory, LaunchError, isMissingTarget } from '../types/app';
 import { Play, Pin, Clock } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/tauri';
 import { AppContextMenu } from './AppContextMenu';
@@ -15,7 +15,8 @@ const AppGrid: React.FC<{
   onPin: (path: string) => void;
   onLaunch: (path: string) => void;
   onMove: (path: string, category: AppCategory) => void;
-}> = ({ apps, isGridView, onPin, onLaunch, onMove }) => {
+  onIconError: (path: string) => void;
+}> = ({ apps, isGridView, onPin, onLaunch, onMove, onIconError }) => {
   const [contextMenu, setContextMenu] = useState<{
     app: AppInfo;
     position: { x: number; y: number };
@@ -78,6 +79,7 @@ const AppGrid: React.FC<{
                       src={app.icon} 
                       alt={app.name}
                       className="app-icon w-20 h-20 mb-4"
+                      onError={() => onIconError(app.path)}
                     />
                   ) : (
                     <div className="w-20 h-20 mb-4 bg-surfaceHover rounded-lg flex items-center justify-center">
@@ -97,6 +99,7 @@ const AppGrid: React.FC<{
                       src={app.icon} 
                       alt={app.name}
                       className="app-icon w-8 h-8"
+                      onError={() => onIconError(app.path)}
                     />
                   ) : (
                     <div className="w-8 h-8 bg-surfaceHover rounded-lg flex items-center justify-center">
@@ -150,16 +153,23 @@ export const AppList: React.FC<AppListProps> = ({ selectedCategory }) => {
     searchTerm, 
     isGridView, 
     togglePinned,
-    updateLastAccessed,
-    updateCategory
+    rankedApps,
+    loadRankedApps,
+    updateCategory,
+    reloadAppIcon
   } = useAppStore();
   
   const handleLaunch = async (path: string) => {
     try {
-      await invoke('launch_app', { path });
-      updateLastAccessed(path, new Date().toISOString());
+      await invoke('launch_app', { path, source: 'app_list' });
+      loadRankedApps();
     } catch (error) {
-      console.error('Failed to launch app:', error);
+      const launchError = error as LaunchError;
+      if (isMissingTarget(launchError)) {
+        console.error('App target is missing, the shortcut may be left over from an uninstall:', launchError);
+      } else if (launchError.kind !== 'elevation_cancelled') {
+        console.error('Failed to launch app:', launchError);
+      }
     }
   };
 
@@ -168,37 +178,42 @@ export const AppList: React.FC<AppListProps> = ({ selectedCategory }) => {
     (selectedCategory === null || app.category === selectedCategory)
   );
 
+  // Position in the backend ranking; apps it doesn't know yet go last
+  const rank = new Map(rankedApps.map((app, index) => [app.path, index]));
+  const rankOf = (app: AppInfo) => rank.get(app.path) ?? rankedApps.length;
+
+  // Search results follow the ranking, the full list stays alphabetical
   const sortedApps = [...filteredApps].sort((a, b) => {
     if (a.isPinned && !b.isPinned) return -1;
     if (!a.isPinned && b.isPinned) return 1;
+    if (searchTerm) return rankOf(a) - rankOf(b) || a.name.localeCompare(b.name);
     return a.name.localeCompare(b.name);
   });
 
-  // Get recent apps by checking lastAccessed timestamps
-  const recentApps = selectedCategory === null 
-    ? filteredApps
-        .filter(app => app.lastAccessed)
-        .sort((a, b) => {
-          const dateA = new Date(a.lastAccessed || '').getTime();
-          const dateB = new Date(b.lastAccessed || '').getTime();
-          return dateB - dateA;
-        })
+  // Most frecent apps that have been launched at all
+  const visibleApps = new Map(filteredApps.map(app => [app.path, app]));
+  const mostUsedApps = selectedCategory === null
+    ? rankedApps
+        .filter(ranked => ranked.launch_count > 0)
+        .map(ranked => visibleApps.get(ranked.path))
+        .filter((app): app is AppInfo => app !== undefined)
         .slice(0, 5)
     : [];
 
   return (
     <div className="p-4">
-      {selectedCategory === null && recentApps.length > 0 && (
+      {selectedCategory === null && mostUsedApps.length > 0 && (
         <div className="mb-8">
           <h2 className="text-sm font-semibold text-textSecondary mb-3 flex items-center gap-2">
-            <Clock className="w-4 h-4" /> Recent
+            <Clock className="w-4 h-4" /> Most Used
           </h2>
           <AppGrid 
-            apps={recentApps}
+            apps={mostUsedApps}
             isGridView={isGridView}
             onPin={togglePinned}
             onLaunch={handleLaunch}
             onMove={updateCategory}
+            onIconError={reloadAppIcon}
           />
         </div>
       )}
@@ -215,6 +230,7 @@ export const AppList: React.FC<AppListProps> = ({ selectedCategory }) => {
           onPin={togglePinned}
           onLaunch={handleLaunch}
           onMove={updateCategory}
+          onIconError={reloadAppIcon}
         />
       </div>
     </div>
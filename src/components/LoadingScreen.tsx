@@ -1,11 +1,14 @@
 import React, { useEffect, useState, useRef } from 'react';
+import { listen } from '@tauri-apps/api/event';
 import { useSettingsStore } from '../store/useSettingsStore';
 import { useAppStore } from '../store/useAppStore';
+import { ScanProgress } from '../types/app';
 
 interface LoadingScreenProps {}
 
 export const LoadingScreen: React.FC<LoadingScreenProps> = () => {
   const [progress, setProgress] = useState(0);
+  const [scanProgress, setScanProgress] = useState<ScanProgress | null>(null);
   const progressIntervalRef = useRef<NodeJS.Timeout | null>(null);
   
   // Get the accent color from the settings store
@@ -16,6 +19,17 @@ export const LoadingScreen: React.FC<LoadingScreenProps> = () => {
   // Get the actual loading state
   const { isLoading } = useAppStore();
   
+  // Live counters reported by the backend scanner
+  useEffect(() => {
+    const unlisten = listen<ScanProgress>('scan://progress', (event) => {
+      setScanProgress(event.payload);
+    });
+
+    return () => {
+      unlisten.then((fn) => fn());
+    };
+  }, []);
+
   // Simulate progress with acceleration when actual loading completes
   useEffect(() => {
     // Clear any existing interval
@@ -71,6 +85,13 @@ export const LoadingScreen: React.FC<LoadingScreenProps> = () => {
         <p className="text-textSecondary text-sm">
           {Math.round(progress)}%
         </p>
+
+        {/* Scanner counters */}
+        {scanProgress && (
+          <p className="text-textSecondary text-xs mt-1">
+            {scanProgress.apps_found} apps found in {scanProgress.directories_visited} folders
+          </p>
+        )}
       </div>
     </div>
   );
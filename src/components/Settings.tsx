@@ -1,14 +1,69 @@
-import React from 'react';
+import React, { useEffect, useState } from 'react';
 import { useSettingsStore } from '../store/useSettingsStore';
 import { useAppStore } from '../store/useAppStore';
-import { Settings as SettingsIcon, Moon, Sun, Monitor, Palette, RotateCcw, MoonStar, RefreshCw } from 'lucide-react';
+import { Settings as SettingsIcon, Moon, Sun, Monitor, Palette, RotateCcw, MoonStar, RefreshCw, AlertTriangle, Search, Trash2 } from 'lucide-react';
 import { useDelayedLoading } from '../hooks/useDelayedLoading';
+import { getAppIndexStats, getBrokenApps, pruneIconCache } from '../lib/system';
+import type { AppIndexStats, BrokenApp, PruneResult } from '../types/app';
+
+const formatBytes = (bytes: number) =>
+  bytes < 1024 * 1024 ? `${Math.round(bytes / 1024)} KB` : `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+
+const describeBrokenApp = ({ reason }: BrokenApp) => {
+  switch (reason.kind) {
+    case 'missing_target':
+      return `target not found: ${reason.target}`;
+    case 'unreachable_network_path':
+      return `network share not reachable: ${reason.share}`;
+    case 'dangling_url':
+      return `address can't be opened: ${reason.url}`;
+  }
+};
 
 export const Settings: React.FC = () => {
   const settings = useSettingsStore();
   const appStore = useAppStore();
   // Show loading animation for at least 800ms for better UX
   const isRefreshing = useDelayedLoading(appStore.isLoading, 800);
+  const [indexStats, setIndexStats] = useState<AppIndexStats | null>(null);
+  // Result of the last shortcut check; null until one has run
+  const [brokenApps, setBrokenApps] = useState<BrokenApp[] | null>(null);
+  const [isCheckingApps, setIsCheckingApps] = useState(false);
+  const [pruneResult, setPruneResult] = useState<PruneResult | null>(null);
+  const [isPruning, setIsPruning] = useState(false);
+
+  // Trim the icon cache to its size limit and delete files no entry uses any more.
+  // Icons that were evicted are extracted again when the list next shows them.
+  const pruneIcons = async () => {
+    setIsPruning(true);
+    try {
+      setPruneResult(await pruneIconCache() as PruneResult);
+    } catch (error) {
+      console.error('Failed to prune the icon cache:', error);
+    } finally {
+      setIsPruning(false);
+    }
+  };
+
+  const checkBrokenApps = async () => {
+    setIsCheckingApps(true);
+    try {
+      setBrokenApps(await getBrokenApps() as BrokenApp[]);
+    } catch (error) {
+      console.error('Failed to check apps:', error);
+    } finally {
+      setIsCheckingApps(false);
+    }
+  };
+
+  // Re-read after every scan so problems with the saved app index show up here
+  useEffect(() => {
+    if (appStore.isLoading) return;
+    getAppIndexStats()
+      .then((stats) => setIndexStats(stats as AppIndexStats))
+      .catch((error) => console.error('Failed to read app index stats:', error));
+  }, [appStore.isLoading]);
+
   const {
     themeMode,
     setThemeMode,
@@ -77,6 +132,69 @@ export const Settings: React.FC = () => {
               <RefreshCw className={`w-4 h-4 ${isRefreshing ? 'animate-spin' : ''}`} />
               {isRefreshing ? 'Refreshing...' : 'Reset App List'}
             </button>
+            {indexStats && (
+              <p className="text-xs text-textSecondary">
+                {indexStats.app_count} apps from {indexStats.shortcut_count} shortcuts
+                {indexStats.last_updated && `, updated ${new Date(indexStats.last_updated * 1000).toLocaleString()}`}
+              </p>
+            )}
+            {indexStats?.load_error && (
+              <p className="text-xs text-red-500 flex items-start gap-2">
+                <AlertTriangle className="w-4 h-4 shrink-0" />
+                The saved app list was unreadable and has been rebuilt: {indexStats.load_error}
+              </p>
+            )}
+            {indexStats?.write_error && (
+              <p className="text-xs text-red-500 flex items-start gap-2">
+                <AlertTriangle className="w-4 h-4 shrink-0" />
+                The app list could not be saved: {indexStats.write_error}
+              </p>
+            )}
+            <button
+              onClick={checkBrokenApps}
+              disabled={isCheckingApps}
+              className={`
+                px-4 py-2 bg-surfaceSecondary
+                ${!isCheckingApps ? 'hover:bg-surfaceHover' : 'opacity-70 cursor-not-allowed'}
+                text-textPrimary rounded-lg flex items-center gap-2 transition-colors
+              `}
+            >
+              <Search className="w-4 h-4" />
+              {isCheckingApps ? 'Checking...' : 'Find Broken Shortcuts'}
+            </button>
+            {brokenApps?.length === 0 && (
+              <p className="text-xs text-textSecondary">Every app in the list can be opened.</p>
+            )}
+            {brokenApps && brokenApps.length > 0 && (
+              <ul className="space-y-1">
+                {brokenApps.map((app) => (
+                  <li key={app.path} className="text-xs text-textSecondary flex items-start gap-2" title={app.path}>
+                    <AlertTriangle className="w-4 h-4 shrink-0 text-red-500" />
+                    <span>
+                      <span className="text-textPrimary">{app.name}</span>: {describeBrokenApp(app)}
+                    </span>
+                  </li>
+                ))}
+              </ul>
+            )}
+            <button
+              onClick={pruneIcons}
+              disabled={isPruning}
+              className={`
+                px-4 py-2 bg-surfaceSecondary
+                ${!isPruning ? 'hover:bg-surfaceHover' : 'opacity-70 cursor-not-allowed'}
+                text-textPrimary rounded-lg flex items-center gap-2 transition-colors
+              `}
+            >
+              <Trash2 className="w-4 h-4" />
+              {isPruning ? 'Cleaning...' : 'Clean Up Icon Cache'}
+            </button>
+            {pruneResult && (
+              <p className="text-xs text-textSecondary">
+                Removed {pruneResult.files_removed} unused icons ({formatBytes(pruneResult.bytes_freed)});
+                the cache now takes {formatBytes(pruneResult.total_bytes)}
+              </p>
+            )}
           </div>
         </div>
 
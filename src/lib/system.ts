@@ -15,7 +15,33 @@ export async function getAppIcon(path: string) {
   return iconData;
 }
 
+// Extract icons for many apps at once; each result arrives as an icons://loaded event
+export async function getAppIcons(paths: string[], size?: number) {
+  const summary = await invoke('get_app_icons', { paths, size });
+  return summary;
+}
+
 export async function getSystemAccentColor() {
   const color = await invoke('get_system_accent_color');
   return color;
-}
\ No newline at end of file
+}
+// Indexed entries whose target is gone or unreachable
+export async function getBrokenApps() {
+  const broken = await invoke('get_broken_apps');
+  return broken;
+}
+
+export async function getRankedApps(query?: string) {
+  const ranked = await invoke('get_ranked_apps', { query });
+  return ranked;
+}
+
+export async function getAppIndexStats() {
+  const stats = await invoke('get_app_index_stats');
+  return stats;
+}
+
+export async function pruneIconCache(maxBytes?: number) {
+  const result = await invoke('prune_icon_cache', { maxBytes });
+  return result;
+}
@@ -1,7 +1,8 @@
 import { create } from 'zustand';
 import { persist } from 'zustand/middleware';
-import { AppInfo } from '../types/app';
-import { loadStartMenuApps, refreshStartMenuApps, getAppIcon } from '../lib/system';
+import { listen } from '@tauri-apps/api/event';
+import { AppChanges, AppInfo, IconResult, RankedApp } from '../types/app';
+import { loadStartMenuApps, refreshStartMenuApps, getAppIcon, getAppIcons, getRankedApps } from '../lib/system';
 
 // Helper functions for icon loading and caching
 // Polyfill for requestIdleCallback
@@ -9,63 +10,72 @@ const requestIdleCallback =
   window.requestIdleCallback ||
   ((callback: IdleRequestCallback) => setTimeout(callback, 1));
 
-// Load icons progressively with priority for visible apps
-const loadIconsProgressively = (
+// Grid tiles show icons at 80 CSS px; ask for enough pixels for the display density
+const ICON_DISPLAY_SIZE = 80;
+const iconSize = () => Math.min(256, Math.ceil(ICON_DISPLAY_SIZE * (window.devicePixelRatio || 1)));
+
+// Load icons in one backend batch; results stream in as icons://loaded events and
+// are applied to the list in small groups to avoid re-rendering per icon
+const loadIconsProgressively = async (
   apps: AppInfo[],
   state: any,
   updateCallback: (apps: AppInfo[]) => void
 ) => {
-  const appsToProcess = [...apps];
   const result = [...apps];
-  
-  // Process icons in small batches to avoid freezing the UI
-  const processBatch = async () => {
-    if (appsToProcess.length === 0) return;
-    
-    // Take first 3 apps from the queue
-    const batch = appsToProcess.splice(0, 3);
-    
-    // Process this batch
-    await Promise.all(batch.map(async (app) => {
-      // Skip if we already have a custom icon
-      if (state.customIcons[app.path]) {
-        return;
-      }
-      
-      // Check cache first
-      const cachedIcon = getIconFromCache(app.path);
-      if (cachedIcon) {
-        const index = result.findIndex(a => a.path === app.path);
-        if (index !== -1) {
-          result[index] = { ...result[index], icon: cachedIcon };
-        }
-        return;
-      }
-      
-      // If not in cache, load from backend
-      try {
-        const icon = await getAppIcon(app.path);
-        const index = result.findIndex(a => a.path === app.path);
-        if (index !== -1) {
-          result[index] = { ...result[index], icon: icon as string };
-          saveIconToCache(app.path, icon as string);
-        }
-      } catch (error) {
-        // Silently fail on icon loading errors
+  const pending: string[] = [];
+
+  for (const app of apps) {
+    // Skip if we already have a custom icon
+    if (state.customIcons[app.path]) {
+      continue;
+    }
+
+    // Check cache first
+    const cachedIcon = getIconFromCache(app.path);
+    if (cachedIcon) {
+      const index = result.findIndex(a => a.path === app.path);
+      if (index !== -1) {
+        result[index] = { ...result[index], icon: cachedIcon };
       }
-    }));
-    
-    // Update UI with latest results
-    updateCallback([...result]);
-    
-    // Process next batch during idle time
-    if (appsToProcess.length > 0) {
-      requestIdleCallback(() => processBatch());
+    } else {
+      pending.push(app.path);
     }
+  }
+
+  updateCallback([...result]);
+  if (pending.length === 0) return;
+
+  let updateScheduled = false;
+  const scheduleUpdate = () => {
+    if (updateScheduled) return;
+    updateScheduled = true;
+    requestIdleCallback(() => {
+      updateScheduled = false;
+      updateCallback([...result]);
+    });
   };
-  
-  // Start processing
-  processBatch();
+
+  const unlisten = await listen<IconResult>('icons://loaded', (event) => {
+    const { path, icon } = event.payload;
+    // Failed entries keep their placeholder
+    if (!icon) return;
+
+    const index = result.findIndex(a => a.path === path);
+    if (index !== -1) {
+      result[index] = { ...result[index], icon };
+      saveIconToCache(path, icon);
+      scheduleUpdate();
+    }
+  });
+
+  try {
+    await getAppIcons(pending, iconSize());
+  } catch (error) {
+    // Silently fail on icon loading errors
+  } finally {
+    unlisten();
+    updateCallback([...result]);
+  }
 };
 
 // Icon cache management
@@ -94,6 +104,18 @@ const getIconFromCache = (path: string): string | null => {
   }
 };
 
+const removeIconFromCache = (path: string): void => {
+  try {
+    localStorage.removeItem(ICON_CACHE_PREFIX + btoa(path));
+  } catch (error) {
+    // Silent error for cache writes
+  }
+};
+
+// Paths whose icon URL already failed once this session; a second failure keeps
+// the placeholder instead of asking the backend again
+const reloadedIcons = new Set<string>();
+
 const saveIconToCache = (path: string, icon: string): void => {
   try {
     const cacheKey = ICON_CACHE_PREFIX + btoa(path);
@@ -116,17 +138,21 @@ interface AppState {
   customIcons: Record<string, string>;
   movedApps: Record<string, string>;
   pinnedApps: string[];
-  lastAccessed: Record<string, string>;
+  // Apps matching the search term as ranked by the backend's usage store, most
+  // frecent first, with paths mapped through movedApps
+  rankedApps: RankedApp[];
   categories: Record<string, string>;
   setApps: (apps: AppInfo[]) => void;
   setSearchTerm: (term: string) => void;
   toggleView: () => void;
   togglePinned: (path: string) => void;
-  updateLastAccessed: (path: string, timestamp: string) => void;
+  loadRankedApps: () => Promise<void>;
   updateCategory: (path: string, category: AppInfo['category']) => void;
   loadApps: () => Promise<void>;
   refreshApps: () => Promise<void>;
+  applyAppChanges: (changes: AppChanges) => void;
   loadAppIcon: (path: string) => Promise<void>;
+  reloadAppIcon: (path: string) => Promise<void>;
   updateAppIcon: (path: string, iconData: string | null) => void;
   moveApp: (path: string, newPath: string) => void;
 }
@@ -139,7 +165,7 @@ const initialState = {
   customIcons: {},
   movedApps: {},
   pinnedApps: [],
-  lastAccessed: {},
+  rankedApps: [],
   categories: {},
 };
 
@@ -149,7 +175,10 @@ export const useAppStore = create<AppState>()(
       ...initialState,
   setApps: (apps) => set({ apps }),
   
-  setSearchTerm: (term) => set({ searchTerm: term }),
+  setSearchTerm: (term) => {
+    set({ searchTerm: term });
+    get().loadRankedApps();
+  },
   
   toggleView: () => {
     set((state) => ({ isGridView: !state.isGridView }));
@@ -173,20 +202,21 @@ export const useAppStore = create<AppState>()(
     });
   },
 
-  updateLastAccessed: (path, timestamp) => {
-    set((state) => {
-      return {
-        lastAccessed: {
-          ...state.lastAccessed,
-          [path]: timestamp
-        },
-        apps: state.apps.map(app => 
-          app.path === path 
-            ? { ...app, lastAccessed: timestamp } 
-            : app
-        )
-      };
-    });
+  // Launches are recorded by the backend, so the ranking is re-read after each one
+  // and whenever the search term changes. Answers for an older term are dropped.
+  loadRankedApps: async () => {
+    const query = get().searchTerm;
+    try {
+      const ranked = await getRankedApps(query) as RankedApp[];
+      if (get().searchTerm !== query) return;
+
+      const { movedApps } = get();
+      set({
+        rankedApps: ranked.map(app => ({ ...app, path: movedApps[app.path] || app.path }))
+      });
+    } catch (error) {
+      // Keep the previous order
+    }
   },
 
   updateCategory: (path, category) => {
@@ -221,14 +251,12 @@ export const useAppStore = create<AppState>()(
         const movedPath = state.movedApps[newApp.path] || newApp.path;
         const category = state.categories[newApp.path] || newApp.category;
         const isPinned = state.pinnedApps.includes(movedPath);
-        const lastAccessed = state.lastAccessed[newApp.path];
 
         return {
           ...newApp,
           path: movedPath,
           category,
           isPinned,
-          lastAccessed,
           // Initialize with placeholder icon
           icon: state.customIcons[movedPath] || null,
         };
@@ -236,6 +264,7 @@ export const useAppStore = create<AppState>()(
       
       // STEP 2: Set apps without icons first for immediate display
       set({ apps: updatedApps, isLoading: false });
+      get().loadRankedApps();
       
       // STEP 3: Load icons in background with priority for visible apps
       requestIdleCallback(() => {
@@ -261,14 +290,12 @@ export const useAppStore = create<AppState>()(
         const movedPath = state.movedApps[newApp.path] || newApp.path;
         const category = state.categories[newApp.path] || newApp.category;
         const isPinned = state.pinnedApps.includes(movedPath);
-        const lastAccessed = state.lastAccessed[newApp.path];
 
         return {
           ...newApp,
           path: movedPath,
           category,
           isPinned,
-          lastAccessed,
           // Initialize with placeholder icon
           icon: state.customIcons[movedPath] || null,
         };
@@ -276,6 +303,7 @@ export const useAppStore = create<AppState>()(
       
       // STEP 2: Set apps without icons first for immediate display
       set({ apps: updatedApps, isLoading: false });
+      get().loadRankedApps();
       
       // STEP 3: Load icons in background with priority for visible apps
       requestIdleCallback(() => {
@@ -289,6 +317,37 @@ export const useAppStore = create<AppState>()(
     }
   },
 
+  // Merge an incremental update pushed by the backend file watcher
+  applyAppChanges: (changes) => {
+    const state = get();
+    const storedPath = (path: string) => state.movedApps[path] || path;
+
+    const dropped = new Set(
+      [...changes.removed, ...changes.updated].map(app => storedPath(app.path))
+    );
+    const incoming = [...changes.updated, ...changes.added].map(newApp => {
+      const movedPath = storedPath(newApp.path);
+      const existing = state.apps.find(app => app.path === movedPath);
+
+      return {
+        ...newApp,
+        path: movedPath,
+        category: state.categories[newApp.path] || newApp.category,
+        isPinned: state.pinnedApps.includes(movedPath),
+        // Keep the icon an updated entry already had
+        icon: state.customIcons[movedPath] || existing?.icon || null,
+      };
+    });
+
+    set({
+      apps: state.apps.filter(app => !dropped.has(app.path)).concat(incoming)
+    });
+
+    incoming
+      .filter(app => !app.icon)
+      .forEach(app => get().loadAppIcon(app.path));
+  },
+
   loadAppIcon: async (path: string) => {
     try {
       // Check if we already have this icon in localStorage cache
@@ -323,6 +382,35 @@ export const useAppStore = create<AppState>()(
     }
   },
 
+  // The backend evicted or pruned the cached PNG behind an icon URL (the protocol
+  // answers 404), so forget the stored URL and have the icon extracted again
+  reloadAppIcon: async (path: string) => {
+    if (get().customIcons[path]) return;
+    removeIconFromCache(path);
+
+    const setIcon = (icon: string | null) => set((state) => ({
+      apps: state.apps.map(app =>
+        app.path === path
+          ? { ...app, icon }
+          : app
+      )
+    }));
+
+    if (reloadedIcons.has(path)) {
+      setIcon(null);
+      return;
+    }
+    reloadedIcons.add(path);
+
+    try {
+      const icon = await getAppIcon(path) as string;
+      saveIconToCache(path, icon);
+      setIcon(icon);
+    } catch (error) {
+      setIcon(null);
+    }
+  },
+
   updateAppIcon: async (path: string, iconData: string | null) => {
     set((state) => {
       const newCustomIcons = { ...state.customIcons };
@@ -366,7 +454,6 @@ export const useAppStore = create<AppState>()(
         customIcons: state.customIcons,
         movedApps: state.movedApps,
         pinnedApps: state.pinnedApps,
-        lastAccessed: state.lastAccessed,
         isGridView: state.isGridView,
         categories: state.categories
       }),
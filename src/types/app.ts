@@ -3,9 +3,96 @@ export type AppCategory = 'Games' | 'Utilities' | 'Media' | 'Development' | 'Oth
 export interface AppInfo {
   name: string;
   path: string;
-  icon?: string | null;
+  icon?: string | null;  // axon-icon:// URL (data URLs from older versions still work)
   customIcon?: string;
   category: AppCategory;
-  lastAccessed?: string;  // ISO string format
+  target?: string | null;
+  arguments?: string | null;
+  working_dir?: string | null;
+  icon_path?: string | null;
+  icon_index?: number;
+  show_command?: number | null;
+  description?: string | null;
+  hotkey?: string | null;  // e.g. "Ctrl+Alt+K"
+  source?: string;
+  shortcuts?: string[];
   isPinned?: boolean;
-}
\ No newline at end of file
+}
+// Payload of the scan://progress event
+export interface ScanProgress {
+  directories_visited: number;
+  apps_found: number;
+}
+
+// Returned by get_app_index_stats
+export interface AppIndexStats {
+  app_count: number;
+  shortcut_count: number;
+  last_updated: number | null;  // Unix time in seconds
+  stale: boolean;
+  load_error: string | null;  // The saved index was unreadable and has been rebuilt
+  write_error: string | null;
+}
+
+// Payload of the apps://changed event
+export interface AppChanges {
+  added: AppInfo[];
+  removed: AppInfo[];
+  updated: AppInfo[];
+}
+
+// Returned by prune_icon_cache
+export interface PruneResult {
+  entries_removed: number;
+  files_removed: number;
+  bytes_freed: number;
+  total_bytes: number;  // Size of the icon cache after pruning
+}
+
+// Payload of the icons://loaded event
+export interface IconResult {
+  path: string;
+  icon: string | null;
+  error: string | null;
+}
+
+// A named launch variant stored per app in settings.launch_profiles
+export interface LaunchProfile {
+  name: string;
+  arguments?: string | null;
+  working_dir?: string | null;
+  run_as_admin?: boolean;
+  window_state?: 'normal' | 'minimized' | 'maximized' | null;
+  env?: Record<string, string>;
+}
+
+// Error returned by launch_app, launch_app_with_profile and shell_open
+export type LaunchError =
+  | { kind: 'file_not_found'; path: string }
+  | { kind: 'path_not_found'; path: string }
+  | { kind: 'access_denied'; path: string }
+  | { kind: 'no_association'; path: string }
+  | { kind: 'out_of_memory' }
+  | { kind: 'elevation_cancelled' }
+  | { kind: 'other'; message: string };
+
+// The target is gone, so the entry is probably left over from an uninstall
+export const isMissingTarget = (error: LaunchError) =>
+  error.kind === 'file_not_found' || error.kind === 'path_not_found';
+
+// An entry reported by get_broken_apps
+export interface BrokenApp {
+  path: string;
+  name: string;
+  reason:
+    | { kind: 'missing_target'; target: string }
+    | { kind: 'unreachable_network_path'; share: string }
+    | { kind: 'dangling_url'; url: string };
+}
+
+// An entry returned by get_ranked_apps, most frecent first
+export interface RankedApp extends AppInfo {
+  score: number;
+  launch_count: number;
+  last_launched: number | null;
+}